@@ -0,0 +1,88 @@
+use crate::parser::{drop_nested, is_container, JsonValue};
+use std::{
+    fmt, mem,
+    ops::{Deref, DerefMut},
+    slice, vec,
+};
+
+/// The elements of a JSON array. Derefs to the `Vec` it wraps, so it can be used like one.
+#[derive(Default, PartialEq)]
+pub struct Array(Vec<JsonValue>);
+
+impl Array {
+    pub fn new() -> Self {
+        Array::default()
+    }
+
+    pub fn into_vec(mut self) -> Vec<JsonValue> {
+        mem::take(&mut self.0)
+    }
+}
+
+// The derived drop would recurse once per nesting level
+impl Drop for Array {
+    fn drop(&mut self) {
+        if self.0.iter().any(is_container) {
+            drop_nested(mem::take(&mut self.0));
+        }
+    }
+}
+
+impl Deref for Array {
+    type Target = Vec<JsonValue>;
+
+    fn deref(&self) -> &Vec<JsonValue> {
+        &self.0
+    }
+}
+
+impl DerefMut for Array {
+    fn deref_mut(&mut self) -> &mut Vec<JsonValue> {
+        &mut self.0
+    }
+}
+
+impl fmt::Debug for Array {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<Vec<JsonValue>> for Array {
+    fn from(elements: Vec<JsonValue>) -> Self {
+        Array(elements)
+    }
+}
+
+impl FromIterator<JsonValue> for Array {
+    fn from_iter<I: IntoIterator<Item = JsonValue>>(iter: I) -> Self {
+        Array(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Array {
+    type Item = JsonValue;
+    type IntoIter = vec::IntoIter<JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_vec().into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Array {
+    type Item = &'a JsonValue;
+    type IntoIter = slice::Iter<'a, JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Array {
+    type Item = &'a mut JsonValue;
+    type IntoIter = slice::IterMut<'a, JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
@@ -0,0 +1,243 @@
+use std::{fmt, io};
+
+/// A location in the source text. `line` and `column` start at 1, `offset` is in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl Position {
+    // Moves past one byte of input, only the first byte of a character counts as a column
+    pub(crate) fn advance(&mut self, byte: u8) {
+        self.offset += 1;
+        if byte == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if byte & 0xC0 != 0x80 {
+            self.column += 1;
+        }
+    }
+}
+
+/// The region of the source covered by a token, `end` is exclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Where an error happened: the offending span and the source around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub span: Span,
+    /// The source line around `span`, as far as the lexer still had it buffered.
+    pub excerpt: String,
+}
+
+impl From<Span> for Location {
+    fn from(span: Span) -> Self {
+        Location {
+            span,
+            excerpt: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Object,
+    Array,
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Container::Object => write!(f, "object"),
+            Container::Array => write!(f, "array"),
+        }
+    }
+}
+
+/// Everything that can go wrong while reading and parsing a document.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The input could not be read.
+    Io(io::Error),
+    /// The input has no content at all.
+    Empty,
+    /// A character that cannot start any token.
+    UnexpectedChar { ch: char, at: Location },
+    /// A valid token in a place the grammar does not allow it.
+    UnexpectedToken {
+        expected: &'static str,
+        at: Location,
+    },
+    /// The input stopped in the middle of a value.
+    UnexpectedEof {
+        expected: &'static str,
+        at: Location,
+    },
+    /// A `,` directly followed by the closing bracket.
+    TrailingComma { container: Container, at: Location },
+    /// A backslash followed by something that is not a JSON escape.
+    InvalidEscape { escape: char, at: Location },
+    /// A `\u` escape that is malformed or does not encode a character.
+    InvalidUnicodeEscape { at: Location },
+    /// Half of a UTF-16 surrogate pair escaped on its own, like `"\ud83d"`.
+    LoneSurrogate { code: u16, at: Location },
+    /// Bytes that do not form valid UTF-8.
+    InvalidUtf8 { at: Location },
+    /// The input ended inside a string, `at` is its opening quote.
+    UnterminatedString { at: Location },
+    /// The input ended inside a `/*` comment, `at` is where it starts.
+    UnterminatedComment { at: Location },
+    /// A control character that must be escaped inside a string.
+    ControlCharacter { ch: char, at: Location },
+    /// A misspelled `true`, `false` or `null`.
+    InvalidLiteral {
+        found: String,
+        expected: &'static str,
+        at: Location,
+    },
+    /// A malformed number, like `01` or `1.`. `reason` says what is wrong with it.
+    InvalidNumber { reason: &'static str, at: Location },
+    /// Arrays and objects nested deeper than [`ParserOptions::max_depth`](crate::ParserOptions::max_depth).
+    DepthExceeded { limit: usize, at: Location },
+    /// A key that already appeared in the same object, with
+    /// [`DuplicateKeys::Error`](crate::DuplicateKeys::Error). `first` is where the
+    /// earlier copy starts.
+    DuplicateKey {
+        key: String,
+        first: Position,
+        at: Location,
+    },
+    /// Anything but whitespace after the top-level value.
+    TrailingCharacters { at: Location },
+    /// A top-level value that is not an object or array, with
+    /// [`ParserOptions::require_container_root`](crate::ParserOptions::require_container_root) set.
+    NonContainerRoot { at: Location },
+}
+
+impl ParseError {
+    /// Where in the input the error was found, if it came from the input at all.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            ParseError::Io(_) | ParseError::Empty => None,
+            ParseError::UnexpectedChar { at, .. }
+            | ParseError::UnexpectedToken { at, .. }
+            | ParseError::UnexpectedEof { at, .. }
+            | ParseError::TrailingComma { at, .. }
+            | ParseError::InvalidEscape { at, .. }
+            | ParseError::InvalidUnicodeEscape { at }
+            | ParseError::LoneSurrogate { at, .. }
+            | ParseError::InvalidUtf8 { at }
+            | ParseError::UnterminatedString { at }
+            | ParseError::UnterminatedComment { at }
+            | ParseError::ControlCharacter { at, .. }
+            | ParseError::InvalidLiteral { at, .. }
+            | ParseError::InvalidNumber { at, .. }
+            | ParseError::DepthExceeded { at, .. }
+            | ParseError::DuplicateKey { at, .. }
+            | ParseError::TrailingCharacters { at }
+            | ParseError::NonContainerRoot { at } => Some(at),
+        }
+    }
+
+    pub(crate) fn location_mut(&mut self) -> Option<&mut Location> {
+        match self {
+            ParseError::Io(_) | ParseError::Empty => None,
+            ParseError::UnexpectedChar { at, .. }
+            | ParseError::UnexpectedToken { at, .. }
+            | ParseError::UnexpectedEof { at, .. }
+            | ParseError::TrailingComma { at, .. }
+            | ParseError::InvalidEscape { at, .. }
+            | ParseError::InvalidUnicodeEscape { at }
+            | ParseError::LoneSurrogate { at, .. }
+            | ParseError::InvalidUtf8 { at }
+            | ParseError::UnterminatedString { at }
+            | ParseError::UnterminatedComment { at }
+            | ParseError::ControlCharacter { at, .. }
+            | ParseError::InvalidLiteral { at, .. }
+            | ParseError::InvalidNumber { at, .. }
+            | ParseError::DepthExceeded { at, .. }
+            | ParseError::DuplicateKey { at, .. }
+            | ParseError::TrailingCharacters { at }
+            | ParseError::NonContainerRoot { at } => Some(at),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "Failed to read file: {}", e),
+            ParseError::Empty => write!(f, "Empty file"),
+            ParseError::UnexpectedChar { ch, .. } => write!(f, "Unexpected character: {}", ch),
+            ParseError::UnexpectedToken { expected, .. } => write!(f, "Expected {}", expected),
+            ParseError::UnexpectedEof { expected, .. } => {
+                write!(f, "Unexpected end of input, expected {}", expected)
+            }
+            ParseError::TrailingComma { container, .. } => {
+                write!(f, "Trailing comma in {}", container)
+            }
+            ParseError::InvalidEscape { escape, .. } => {
+                write!(f, "Invalid escape sequence: \\{}", escape)
+            }
+            ParseError::InvalidUnicodeEscape { .. } => write!(f, "Invalid Unicode escape sequence"),
+            ParseError::LoneSurrogate { code, .. } => {
+                write!(f, "Unpaired surrogate in Unicode escape: \\u{:04x}", code)
+            }
+            ParseError::InvalidUtf8 { .. } => write!(f, "Invalid UTF-8 in input"),
+            ParseError::UnterminatedString { .. } => write!(f, "Unterminated string"),
+            ParseError::UnterminatedComment { .. } => write!(f, "Unterminated comment"),
+            ParseError::ControlCharacter { ch, .. } => {
+                write!(f, "Invalid unescaped control character in string: {:?}", ch)
+            }
+            ParseError::InvalidLiteral {
+                found, expected, ..
+            } => write!(f, "Invalid literal {}, expected {}", found, expected),
+            ParseError::InvalidNumber { reason, .. } => write!(f, "{}", reason),
+            ParseError::DepthExceeded { limit, .. } => {
+                write!(f, "Exceeded maximum nesting depth of {}", limit)
+            }
+            ParseError::DuplicateKey { key, first, .. } => write!(
+                f,
+                "Duplicate key {:?}, first seen at line {} column {}",
+                key, first.line, first.column
+            ),
+            ParseError::TrailingCharacters { .. } => {
+                write!(f, "Extra tokens after top-level value")
+            }
+            ParseError::NonContainerRoot { .. } => {
+                write!(f, "A JSON payload should be an object or array")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
@@ -0,0 +1,920 @@
+use crate::error::{ParseError, Position, Span};
+use crate::number::{hex_to_decimal, Number};
+use crate::options::{Dialect, LoneSurrogates, ParserOptions};
+use std::io::{BufRead, ErrorKind};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+const EXCERPT_RADIUS: usize = 30;
+const RECORD_SEPARATOR: u8 = 0x1E;
+// How much of a single line is kept for excerpts, long minified lines get cut from the front
+const LINE_BUFFER_LIMIT: usize = 1024;
+
+#[derive(Debug)]
+pub(crate) enum Token {
+    CurlyOpen,
+    CurlyClose,
+    SquareOpen,
+    SquareClose,
+    Colon,
+    Comma,
+    String(String),
+    // A string with a lone surrogate kept by `LoneSurrogates::Preserve`, as UTF-16 code
+    // units. `lone` is the first such surrogate, for keys where it's still an error
+    Utf16String { units: Vec<u16>, lone: (u16, Span) },
+    // An unquoted JSON5 object key
+    Identifier(String),
+    Number(Number),
+    Bool(bool),
+    Null,
+    Eof,
+}
+
+#[derive(Debug)]
+pub(crate) struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+/// The tail of one source line, kept so errors can quote the input without holding all of it.
+struct LineBuffer {
+    line: usize,
+    // Column of the first byte still in `bytes`
+    column: usize,
+    bytes: Vec<u8>,
+    has_token: bool,
+}
+
+impl LineBuffer {
+    fn new(line: usize) -> Self {
+        LineBuffer {
+            line,
+            column: 1,
+            bytes: Vec::new(),
+            has_token: false,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.bytes.len() == LINE_BUFFER_LIMIT {
+            let dropped = self.bytes.drain(..LINE_BUFFER_LIMIT / 2);
+            self.column += dropped.filter(|b| !is_continuation(*b)).count();
+        }
+        self.bytes.push(byte);
+    }
+
+    // Cut the line down to a few characters on each side of `column`
+    fn excerpt(&self, column: usize, lookahead: &[u8]) -> String {
+        let mut bytes = self.bytes.clone();
+        bytes.extend_from_slice(lookahead);
+        let index = column.saturating_sub(self.column);
+        let skip = index.saturating_sub(EXCERPT_RADIUS);
+        String::from_utf8_lossy(&bytes)
+            .chars()
+            .skip(skip)
+            .take(index - skip + EXCERPT_RADIUS)
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// Pulls tokens one at a time out of a buffered reader, so the input never has to fit in memory.
+pub(crate) struct Lexer<R> {
+    reader: R,
+    options: ParserOptions,
+    position: Position,
+    // When false, string contents are checked but not collected
+    keep_values: bool,
+    // RFC 7464 input, where a record separator ends the current document like end of input
+    record_separated: bool,
+    // Reused for number and keyword lexemes so they don't allocate every time
+    scratch: String,
+    peeked: Option<SpannedToken>,
+    current_line: LineBuffer,
+    // The last finished line that had a token on it, the parser may still report errors there
+    token_line: LineBuffer,
+}
+
+impl<R: BufRead> Lexer<R> {
+    pub fn new(reader: R, options: ParserOptions) -> Self {
+        Lexer {
+            reader,
+            options,
+            position: Position::default(),
+            keep_values: true,
+            record_separated: false,
+            scratch: String::new(),
+            peeked: None,
+            current_line: LineBuffer::new(1),
+            token_line: LineBuffer::new(0),
+        }
+    }
+
+    /// A lexer for input that continues a bigger document at `position`, so errors point
+    /// into the whole document.
+    pub fn starting_at(reader: R, options: ParserOptions, position: Position) -> Self {
+        Lexer {
+            position,
+            current_line: LineBuffer::new(position.line),
+            ..Lexer::new(reader, options)
+        }
+    }
+
+    /// A lexer for an RFC 7464 JSON text sequence, which sees the end of input at every
+    /// record separator (0x1E) until [`next_record`](Lexer::next_record) moves past it.
+    pub fn record_separated(reader: R, options: ParserOptions) -> Self {
+        Lexer {
+            record_separated: true,
+            ..Lexer::new(reader, options)
+        }
+    }
+
+    /// A lexer that only checks the input, its string tokens are always empty.
+    pub fn without_values(reader: R, options: ParserOptions) -> Self {
+        Lexer {
+            keep_values: false,
+            ..Lexer::new(reader, options)
+        }
+    }
+
+    pub fn peek_token(&mut self) -> Result<&SpannedToken> {
+        if self.peeked.is_none() {
+            self.peeked = Some(tokenize(self)?);
+        }
+        Ok(self.peeked.as_ref().expect("token was just read"))
+    }
+
+    pub fn next_token(&mut self) -> Result<SpannedToken> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => tokenize(self),
+        }
+    }
+
+    /// Fills in the excerpt of `error` from the lines that are still buffered.
+    pub fn with_excerpt(&mut self, mut error: ParseError) -> ParseError {
+        if let Some(at) = error.location_mut() {
+            let start = at.span.start;
+            if start.line == self.current_line.line {
+                let lookahead = self.line_lookahead();
+                at.excerpt = self.current_line.excerpt(start.column, &lookahead);
+            } else if start.line == self.token_line.line {
+                at.excerpt = self.token_line.excerpt(start.column, &[]);
+            }
+        }
+        error
+    }
+
+    // Whatever is already buffered of the rest of the current line
+    fn line_lookahead(&mut self) -> Vec<u8> {
+        let buffer = self.reader.fill_buf().unwrap_or_default();
+        buffer
+            .iter()
+            .take(EXCERPT_RADIUS * 4)
+            .take_while(|&&b| b != b'\n')
+            .copied()
+            .collect()
+    }
+
+    /// Skips the rest of the current record, however broken it is, and the separators
+    /// after it. False once the input is used up.
+    pub fn next_record(&mut self) -> Result<bool> {
+        self.peeked = None;
+        loop {
+            match self.peek_raw()? {
+                None => return Ok(false),
+                Some(RECORD_SEPARATOR) => break,
+                Some(byte) => self.consume(byte),
+            }
+        }
+        while self.peek_raw()? == Some(RECORD_SEPARATOR) {
+            self.consume(RECORD_SEPARATOR);
+        }
+        Ok(true)
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        let byte = self.peek_raw()?;
+        if self.record_separated && byte == Some(RECORD_SEPARATOR) {
+            return Ok(None);
+        }
+        Ok(byte)
+    }
+
+    fn peek_raw(&mut self) -> Result<Option<u8>> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buffer) => return Ok(buffer.first().copied()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<u8>> {
+        let byte = self.peek()?;
+        if let Some(byte) = byte {
+            self.consume(byte);
+        }
+        Ok(byte)
+    }
+
+    // Moves past `byte`, which was just peeked
+    fn consume(&mut self, byte: u8) {
+        self.reader.consume(1);
+        self.position.advance(byte);
+        if byte == b'\n' {
+            let finished =
+                std::mem::replace(&mut self.current_line, LineBuffer::new(self.position.line));
+            if finished.has_token {
+                self.token_line = finished;
+            }
+        } else {
+            self.current_line.push(byte);
+        }
+    }
+
+    fn is_json5(&self) -> bool {
+        self.options.dialect == Dialect::Json5
+    }
+
+    fn take_scratch(&mut self) -> String {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.clear();
+        scratch
+    }
+
+    fn span_from(&self, start: Position) -> Span {
+        Span {
+            start,
+            end: self.position,
+        }
+    }
+
+    // Span of the next byte, or an empty span at the end of input
+    fn next_span(&mut self) -> Span {
+        let start = self.position;
+        let mut end = start;
+        if let Ok(Some(byte)) = self.peek() {
+            end.advance(byte);
+        }
+        Span { start, end }
+    }
+
+    // Decodes the next (possibly multi-byte) character
+    fn next_char(&mut self) -> Result<Option<char>> {
+        let start = self.position;
+        let Some(first) = self.next()? else {
+            return Ok(None);
+        };
+        let width = match first {
+            0x00..=0x7F => return Ok(Some(first as char)),
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => 0,
+        };
+
+        let mut bytes = vec![first];
+        while bytes.len() < width {
+            match self.peek()? {
+                Some(byte) if is_continuation(byte) => {
+                    bytes.push(byte);
+                    self.next()?;
+                }
+                _ => break,
+            }
+        }
+
+        match std::str::from_utf8(&bytes) {
+            Ok(s) if width > 0 => Ok(s.chars().next()),
+            _ => Err(ParseError::InvalidUtf8 {
+                at: self.span_from(start).into(),
+            }),
+        }
+    }
+}
+
+// Reads the hex digits of a `\u` (four) or JSON5 `\x` (two) escape, `start` is where the
+// backslash was. `quote` is the string's opening quote, which is where running out of
+// input gets reported
+fn tokenize_hex_escape<R: BufRead>(
+    lexer: &mut Lexer<R>,
+    start: Position,
+    quote: Span,
+    digits: usize,
+) -> Result<u32> {
+    let mut code = 0;
+    for _ in 0..digits {
+        if let Some(hex_digit) = lexer.peek()? {
+            if let Some(digit) = (hex_digit as char).to_digit(16) {
+                code = code * 16 + digit;
+                lexer.next()?;
+            } else {
+                return Err(ParseError::InvalidUnicodeEscape {
+                    at: lexer.span_from(start).into(),
+                });
+            }
+        } else {
+            return Err(ParseError::UnterminatedString { at: quote.into() });
+        }
+    }
+
+    Ok(code)
+}
+
+/// One step through a string literal.
+enum StringPart {
+    Char(char),
+    // A `\u` escape, which might be half of a surrogate pair
+    CodeUnit(u32, Span),
+    // A JSON5 backslash before a line break, which stands for nothing
+    LineContinuation,
+    End,
+}
+
+// `delimiter` is the quote character the string started with
+fn tokenize_string_part<R: BufRead>(
+    lexer: &mut Lexer<R>,
+    quote: Span,
+    delimiter: u8,
+) -> Result<StringPart> {
+    let Some(byte) = lexer.peek()? else {
+        return Err(ParseError::UnterminatedString { at: quote.into() });
+    };
+    let is_json5 = lexer.is_json5();
+
+    match byte {
+        b'\\' => {
+            let escape_start = lexer.position;
+            lexer.next()?; // Skip the backslash
+            let Some(escaped_byte) = lexer.peek()? else {
+                return Err(ParseError::UnterminatedString { at: quote.into() });
+            };
+            let unescaped = match escaped_byte {
+                b'"' => '"',
+                b'\\' => '\\',
+                b'/' => '/',
+                b'b' => '\x08', // Backspace rust don't like \b in char
+                b'f' => '\x0C', // Form feed rust don't like \f in char
+                b'n' => '\n',
+                b'r' => '\r',
+                b't' => '\t',
+                b'u' => {
+                    lexer.next()?; // Skip 'u'
+                    let code = tokenize_hex_escape(lexer, escape_start, quote, 4)?;
+                    return Ok(StringPart::CodeUnit(code, lexer.span_from(escape_start)));
+                }
+                b'x' if is_json5 => {
+                    lexer.next()?; // Skip 'x'
+                    let code = tokenize_hex_escape(lexer, escape_start, quote, 2)?;
+                    return Ok(StringPart::Char(code as u8 as char));
+                }
+                b'\'' if is_json5 => '\'',
+                b'v' if is_json5 => '\x0B',
+                b'0' if is_json5 => {
+                    // Skip '0', which can't be followed by a digit, that would be an octal
+                    // escape in JavaScript
+                    lexer.next()?;
+                    if matches!(lexer.peek()?, Some(b'0'..=b'9')) {
+                        return Err(ParseError::InvalidEscape {
+                            escape: '0',
+                            at: lexer.span_from(escape_start).into(),
+                        });
+                    }
+                    return Ok(StringPart::Char('\0'));
+                }
+                b'\r' | b'\n' if is_json5 => {
+                    lexer.next()?;
+                    if escaped_byte == b'\r' && lexer.peek()? == Some(b'\n') {
+                        lexer.next()?;
+                    }
+                    return Ok(StringPart::LineContinuation);
+                }
+                _ => {
+                    let escape = lexer.next_char()?.unwrap_or_default();
+                    // JSON5 lets any other character escape to itself, except digits
+                    return match escape {
+                        '\u{2028}' | '\u{2029}' if is_json5 => Ok(StringPart::LineContinuation),
+                        _ if is_json5 && !escape.is_ascii_digit() => Ok(StringPart::Char(escape)),
+                        _ => Err(ParseError::InvalidEscape {
+                            escape,
+                            at: lexer.span_from(escape_start).into(),
+                        }),
+                    };
+                }
+            };
+            lexer.next()?; // Skip the escaped character
+            Ok(StringPart::Char(unescaped))
+        }
+        _ if byte == delimiter => {
+            lexer.next()?; // Skip closing quote
+            Ok(StringPart::End)
+        }
+        _ => {
+            let start = lexer.position;
+            let ch = lexer.next_char()?.unwrap_or_default();
+            // RFC 8259 wants U+0000 to U+001F escaped, anything else can appear as is.
+            // JSON5 only insists on line breaks being escaped
+            let must_escape = if is_json5 {
+                matches!(ch, '\n' | '\r')
+            } else {
+                ch < ' '
+            };
+            if must_escape {
+                return Err(ParseError::ControlCharacter {
+                    ch,
+                    at: lexer.span_from(start).into(),
+                });
+            }
+            Ok(StringPart::Char(ch))
+        }
+    }
+}
+
+// The contents of a string literal: text until a preserved lone surrogate turns up, then
+// UTF-16 code units, since a Rust string can't hold one
+enum StringValue {
+    Text(String),
+    Utf16(Vec<u16>),
+}
+
+impl StringValue {
+    fn push(&mut self, ch: char) {
+        match self {
+            StringValue::Text(text) => text.push(ch),
+            StringValue::Utf16(units) => units.extend(ch.encode_utf16(&mut [0; 2]).iter()),
+        }
+    }
+
+    fn push_unit(&mut self, unit: u16) {
+        if let StringValue::Text(text) = self {
+            *self = StringValue::Utf16(text.encode_utf16().collect());
+        }
+        if let StringValue::Utf16(units) = self {
+            units.push(unit);
+        }
+    }
+}
+
+fn push_lone_surrogate<R: BufRead>(
+    lexer: &Lexer<R>,
+    value: &mut StringValue,
+    first_lone: &mut Option<(u16, Span)>,
+    code: u32,
+    span: Span,
+) -> Result<()> {
+    let code = code as u16;
+    match lexer.options.lone_surrogates {
+        LoneSurrogates::Error => {
+            return Err(ParseError::LoneSurrogate {
+                code,
+                at: span.into(),
+            })
+        }
+        LoneSurrogates::Replace => {
+            if lexer.keep_values {
+                value.push(char::REPLACEMENT_CHARACTER);
+            }
+        }
+        LoneSurrogates::Preserve => {
+            first_lone.get_or_insert((code, span));
+            if lexer.keep_values {
+                value.push_unit(code);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn tokenize_string<R: BufRead>(lexer: &mut Lexer<R>) -> Result<Token> {
+    let mut value = StringValue::Text(String::new());
+    let mut first_lone = None;
+    // A high surrogate waiting to see if a low one follows it
+    let mut high_surrogate: Option<(u32, Span)> = None;
+    let quote = lexer.next_span();
+    let delimiter = lexer.next()?.unwrap_or(b'"'); // Skip opening quote
+
+    loop {
+        let part = tokenize_string_part(lexer, quote, delimiter)?;
+
+        if let Some((high, high_span)) = high_surrogate.take() {
+            if let StringPart::CodeUnit(low @ 0xDC00..=0xDFFF, _) = part {
+                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                match char::from_u32(code) {
+                    Some(ch) if lexer.keep_values => value.push(ch),
+                    _ => {}
+                }
+                continue;
+            }
+            push_lone_surrogate(lexer, &mut value, &mut first_lone, high, high_span)?;
+        }
+
+        match part {
+            StringPart::Char(ch) => {
+                if lexer.keep_values {
+                    value.push(ch);
+                }
+            }
+            StringPart::CodeUnit(code @ 0xD800..=0xDBFF, span) => {
+                high_surrogate = Some((code, span));
+            }
+            StringPart::CodeUnit(code, span) => match char::from_u32(code) {
+                Some(ch) if lexer.keep_values => value.push(ch),
+                Some(_) => {}
+                None => push_lone_surrogate(lexer, &mut value, &mut first_lone, code, span)?,
+            },
+            StringPart::LineContinuation => {}
+            StringPart::End => break,
+        }
+    }
+
+    Ok(match (value, first_lone) {
+        (StringValue::Text(text), None) => Token::String(text),
+        (StringValue::Utf16(units), Some(lone)) => Token::Utf16String { units, lone },
+        // Only when values aren't kept
+        (StringValue::Text(_), Some(lone)) => Token::Utf16String {
+            units: Vec::new(),
+            lone,
+        },
+        (StringValue::Utf16(_), None) => unreachable!("code units only come from lone surrogates"),
+    })
+}
+
+/// Where the number lexer is in the RFC 8259 `number` production:
+/// `[ minus ] int [ frac ] [ exp ]`, plus the extra shapes JSON5 allows.
+#[derive(Clone, Copy)]
+enum NumberState {
+    Start,
+    Sign,
+    // A leading `0`, which can't be followed by more digits
+    Zero,
+    Integer,
+    Point,
+    // JSON5 `.5`, a point with no integer part
+    LeadingPoint,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+    // JSON5 `0x`, then its digits
+    Hex,
+    HexDigits,
+}
+
+// JSON5 numbers are rewritten as they are read so the lexeme is always plain JSON,
+// `+.5` becomes `0.5` and `0x1F` becomes `31`
+fn tokenize_number<R: BufRead>(lexer: &mut Lexer<R>) -> Result<Number> {
+    use NumberState::*;
+
+    let start = lexer.position;
+    let is_json5 = lexer.is_json5();
+    let mut result = lexer.take_scratch();
+    let mut state = Start;
+
+    loop {
+        let byte = lexer.peek()?;
+        let next = match (state, byte) {
+            (Start, Some(b'-')) => Sign,
+            (Start, Some(b'+')) if is_json5 => Sign,
+            (Start, Some(b'+')) => {
+                return Err(ParseError::InvalidNumber {
+                    reason: "Numbers can't start with a plus sign",
+                    at: lexer.next_span().into(),
+                })
+            }
+            (Start | Sign, Some(b'I' | b'N')) if is_json5 => {
+                let negative = result.starts_with('-');
+                lexer.scratch = result;
+                return tokenize_non_finite(lexer, start, negative);
+            }
+            (Start | Sign, Some(b'0')) => Zero,
+            (Start | Sign, Some(b'1'..=b'9')) => Integer,
+            (Start | Sign, Some(b'.')) if is_json5 => LeadingPoint,
+            (Zero, Some(b'x' | b'X')) if is_json5 => Hex,
+            (Zero, Some(b'0'..=b'9')) => {
+                return Err(ParseError::InvalidNumber {
+                    reason: "Invalid number with leading zero",
+                    at: lexer.next_span().into(),
+                })
+            }
+            (Integer, Some(b'0'..=b'9')) => Integer,
+            (Zero | Integer, Some(b'.')) => Point,
+            (Point | LeadingPoint | Fraction, Some(b'0'..=b'9')) => Fraction,
+            (Zero | Integer | Fraction, Some(b'e' | b'E')) => Exponent,
+            (Point, Some(b'e' | b'E')) if is_json5 => Exponent,
+            (Exponent, Some(b'-' | b'+')) => ExponentSign,
+            (Exponent | ExponentSign | ExponentDigits, Some(b'0'..=b'9')) => ExponentDigits,
+            (Hex | HexDigits, Some(b'0'..=b'9' | b'a'..=b'f' | b'A'..=b'F')) => HexDigits,
+            // Anything else ends the number, which is fine as long as it's complete
+            (Zero | Integer | Fraction | ExponentDigits | HexDigits, _) => break,
+            (Point, _) if is_json5 => break,
+            (Start | Sign, _) => {
+                // A JSON5 `+` isn't kept in the lexeme, a `-` is
+                let reason = if result.starts_with('-') {
+                    "Expected digits after minus sign"
+                } else {
+                    "Expected digits after plus sign"
+                };
+                return Err(ParseError::InvalidNumber {
+                    reason,
+                    at: lexer.next_span().into(),
+                });
+            }
+            (Point | LeadingPoint, _) => {
+                return Err(ParseError::InvalidNumber {
+                    reason: "Expected digits after decimal point",
+                    at: lexer.next_span().into(),
+                })
+            }
+            (Exponent | ExponentSign, _) => {
+                return Err(ParseError::InvalidNumber {
+                    reason: "Expected digits in exponent",
+                    at: lexer.next_span().into(),
+                })
+            }
+            (Hex, _) => {
+                return Err(ParseError::InvalidNumber {
+                    reason: "Expected hex digits after 0x",
+                    at: lexer.next_span().into(),
+                })
+            }
+        };
+
+        // Only ASCII bytes get this far
+        let byte = byte.unwrap_or_default();
+        match (state, next) {
+            (Start, Sign) if byte == b'+' => {}
+            (_, LeadingPoint) => result.push_str("0."),
+            (Point, Exponent) => {
+                result.pop(); // `1.e5` is written `1e5` in JSON
+                result.push(byte as char);
+            }
+            (Zero, Hex) => {
+                result.pop(); // Only the hex digits are kept
+            }
+            _ => result.push(byte as char),
+        }
+        lexer.next()?;
+        state = next;
+    }
+
+    match state {
+        Point => {
+            result.pop(); // A trailing point, `5.` is just `5`
+        }
+        HexDigits => result = hex_to_decimal(&result),
+        _ => {}
+    }
+
+    if lexer.keep_values {
+        Ok(Number::from_lexeme(result))
+    } else {
+        lexer.scratch = result;
+        Ok(Number::from_lexeme(String::new()))
+    }
+}
+
+// JSON5 `Infinity` and `NaN`, after any sign. Their lexeme is kept even when values
+// aren't, the parser needs it to tell `{NaN: 1}` apart from `{1: 1}`
+fn tokenize_non_finite<R: BufRead>(
+    lexer: &mut Lexer<R>,
+    start: Position,
+    negative: bool,
+) -> Result<Number> {
+    let mut word = lexer.take_scratch();
+    while let Some(byte) = lexer.peek()? {
+        if !byte.is_ascii_alphanumeric() {
+            break;
+        }
+        word.push(byte as char);
+        lexer.next()?;
+    }
+
+    let lexeme = match word.as_str() {
+        "Infinity" if negative => "-Infinity",
+        "Infinity" => "Infinity",
+        "NaN" => "NaN",
+        _ => {
+            let expected = if word.starts_with('I') {
+                "Infinity"
+            } else {
+                "NaN"
+            };
+            return Err(ParseError::InvalidLiteral {
+                found: word,
+                expected,
+                at: lexer.span_from(start).into(),
+            });
+        }
+    };
+    lexer.scratch = word;
+    Ok(Number::from_lexeme(lexeme.to_string()))
+}
+
+// Reads a whole run of letters and digits so typos like `fasle` or `nul` are caught
+// instead of being cut short at the first letter that doesn't fit
+fn tokenize_keyword<R: BufRead>(lexer: &mut Lexer<R>, start: Position) -> Result<Token> {
+    let mut result = lexer.take_scratch();
+
+    while let Some(byte) = lexer.peek()? {
+        if !byte.is_ascii_alphanumeric() {
+            break;
+        }
+        result.push(byte as char);
+        lexer.next()?;
+    }
+
+    let token = match result.as_str() {
+        "true" => Token::Bool(true),
+        "false" => Token::Bool(false),
+        "null" => Token::Null,
+        _ => {
+            let expected = match result.as_bytes()[0] {
+                b't' => "true",
+                b'f' => "false",
+                _ => "null",
+            };
+            return Err(ParseError::InvalidLiteral {
+                found: result,
+                expected,
+                at: lexer.span_from(start).into(),
+            });
+        }
+    };
+    lexer.scratch = result;
+    Ok(token)
+}
+
+// A JSON5 identifier, which is either a literal or an object key. `word` already holds
+// its first character when that wasn't ASCII
+fn tokenize_identifier<R: BufRead>(lexer: &mut Lexer<R>, mut word: String) -> Result<Token> {
+    loop {
+        match lexer.peek()? {
+            Some(byte) if byte.is_ascii_alphanumeric() || byte == b'$' || byte == b'_' => {
+                word.push(byte as char);
+                lexer.next()?;
+            }
+            Some(0x80..) => {
+                let char_start = lexer.position;
+                let ch = lexer.next_char()?.unwrap_or_default();
+                if ch.is_whitespace() {
+                    break;
+                }
+                if !ch.is_alphanumeric() {
+                    return Err(ParseError::UnexpectedChar {
+                        ch,
+                        at: lexer.span_from(char_start).into(),
+                    });
+                }
+                word.push(ch);
+            }
+            _ => break,
+        }
+    }
+
+    let token = match word.as_str() {
+        "true" => Token::Bool(true),
+        "false" => Token::Bool(false),
+        "null" => Token::Null,
+        "Infinity" | "NaN" => Token::Number(Number::from_lexeme(word.clone())),
+        _ if lexer.keep_values => return Ok(Token::Identifier(word)),
+        _ => Token::Identifier(String::new()),
+    };
+    lexer.scratch = word;
+    Ok(token)
+}
+
+// Skips a `//` or `/* */` comment, `start` is where its slash was
+fn skip_comment<R: BufRead>(lexer: &mut Lexer<R>, start: Position) -> Result<()> {
+    lexer.next()?; // Skip the first '/'
+    match lexer.peek()? {
+        Some(b'/') => {
+            while let Some(byte) = lexer.next()? {
+                if byte == b'\n' {
+                    break;
+                }
+            }
+        }
+        Some(b'*') => {
+            lexer.next()?;
+            let mut previous = 0;
+            loop {
+                match lexer.next()? {
+                    Some(b'/') if previous == b'*' => break,
+                    Some(byte) => previous = byte,
+                    None => {
+                        return Err(ParseError::UnterminatedComment {
+                            at: Span {
+                                start,
+                                end: Position {
+                                    offset: start.offset + 2,
+                                    column: start.column + 2,
+                                    ..start
+                                },
+                            }
+                            .into(),
+                        })
+                    }
+                }
+            }
+        }
+        _ => {
+            return Err(ParseError::UnexpectedChar {
+                ch: '/',
+                at: lexer.span_from(start).into(),
+            })
+        }
+    }
+    Ok(())
+}
+
+// Reads the next token, skipping any whitespace in front of it
+fn tokenize<R: BufRead>(lexer: &mut Lexer<R>) -> Result<SpannedToken> {
+    if lexer.position.offset == 0 && !lexer.record_separated && lexer.peek()?.is_none() {
+        return Err(ParseError::Empty);
+    }
+
+    loop {
+        let start = lexer.position;
+        let Some(byte) = lexer.peek()? else {
+            return Ok(SpannedToken {
+                token: Token::Eof,
+                span: lexer.span_from(start),
+            });
+        };
+
+        let token = match byte {
+            b'{' => {
+                lexer.next()?;
+                Token::CurlyOpen
+            }
+            b'}' => {
+                lexer.next()?;
+                Token::CurlyClose
+            }
+            b'[' => {
+                lexer.next()?;
+                Token::SquareOpen
+            }
+            b']' => {
+                lexer.next()?;
+                Token::SquareClose
+            }
+            b':' => {
+                lexer.next()?;
+                Token::Colon
+            }
+            b',' => {
+                lexer.next()?;
+                Token::Comma
+            }
+            b'"' => tokenize_string(lexer)?,
+            b'\'' if lexer.is_json5() => tokenize_string(lexer)?,
+            b'0'..=b'9' | b'-' | b'+' => Token::Number(tokenize_number(lexer)?),
+            b'.' if lexer.is_json5() => Token::Number(tokenize_number(lexer)?),
+            b'a'..=b'z' | b'A'..=b'Z' | b'$' | b'_' if lexer.is_json5() => {
+                let word = lexer.take_scratch();
+                tokenize_identifier(lexer, word)?
+            }
+            b't' | b'f' | b'n' => tokenize_keyword(lexer, start)?,
+            b'/' if lexer.options.dialect.allows_comments() => {
+                skip_comment(lexer, start)?;
+                continue;
+            }
+            _ => {
+                let ch = lexer.next_char()?.unwrap_or_default();
+                // JSON5 takes any Unicode whitespace and the byte order mark, JSON and
+                // JSONC only these four
+                let is_whitespace = if lexer.is_json5() {
+                    ch.is_whitespace() || ch == '\u{FEFF}'
+                } else {
+                    matches!(ch, ' ' | '\t' | '\n' | '\r')
+                };
+                if is_whitespace {
+                    continue;
+                }
+                if !(lexer.is_json5() && ch.is_alphabetic()) {
+                    return Err(ParseError::UnexpectedChar {
+                        ch,
+                        at: lexer.span_from(start).into(),
+                    });
+                }
+                let mut word = lexer.take_scratch();
+                word.push(ch);
+                tokenize_identifier(lexer, word)?
+            }
+        };
+
+        lexer.current_line.has_token = true;
+        return Ok(SpannedToken {
+            token,
+            span: lexer.span_from(start),
+        });
+    }
+}
@@ -0,0 +1,24 @@
+//! A small JSON parser, usable as a library or through the `cc-json-parser` binary.
+
+mod array;
+mod error;
+mod lexer;
+mod map;
+mod number;
+mod options;
+mod parser;
+mod serializer;
+mod stream;
+mod tests;
+
+pub use array::Array;
+pub use error::{Container, Location, ParseError, Position, Span};
+pub use map::{Map, MapEntry, MapIter, MapIterMut, OccupiedEntry, VacantEntry};
+pub use number::{Decimal, Number};
+pub use options::{Dialect, DuplicateKeys, LoneSurrogates, ParserOptions};
+pub use parser::{
+    parse_json, parse_reader, parse_slice, parse_str, validate_json, validate_reader,
+    validate_slice, validate_str, JsonValue,
+};
+pub use serializer::{Indent, PrettyOptions, SerializeOptions};
+pub use stream::{document_reader, ndjson_reader, DocumentReader, Framing, NdjsonReader};
@@ -1,29 +1,208 @@
-mod parser;
-mod tests;
+use cc_json_parser::{
+    Dialect, Indent, JsonValue, ParseError, ParserOptions, PrettyOptions, SerializeOptions,
+};
+use std::{
+    env,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    process::exit,
+};
 
-use parser::parse_json;
-use std::{env, process::exit};
+const USAGE: &str =
+    "usage: cc-json-parser [--json5 | --jsonc] [validate | pretty [--indent <n> | --tabs] [--width <n>]] <file>
+       cc-json-parser [--json5 | --jsonc] minify <file> [output]
+       cc-json-parser [validate] --ndjson <file>";
+
+// Wider than anyone lays out JSON by hand
+const MAX_INDENT: usize = 16;
+
+enum Command {
+    Parse,
+    // Only reports PASS/FAIL and never builds the parsed value
+    Validate,
+    // Prints the document laid out for humans, with nothing else on stdout
+    Pretty(PrettyOptions),
+    // Writes the document with no insignificant whitespace, to stdout unless given a file
+    Minify(Option<String>),
+    // Checks newline-delimited JSON one line at a time, picked for .ndjson and .jsonl files
+    ValidateLines,
+}
 
 fn main() {
-    let mut args = env::args().skip(1);
+    let (command, options, file_path) = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            eprintln!("{}", USAGE);
+            exit(1);
+        }
+    };
 
-    let file_path = args.next();
+    match command {
+        Command::Validate => match validate(&options, &file_path) {
+            Ok(()) => println!("PASS"),
+            Err(e) => {
+                println!("FAIL\n{}", describe(&file_path, &e));
+                exit(1);
+            }
+        },
+        Command::Parse => match parse(&options, &file_path) {
+            Ok(r) => println!("PASS\n{}", r),
+            Err(e) => {
+                println!("FAIL\n{}", describe(&file_path, &e));
+                exit(1);
+            }
+        },
+        Command::Pretty(pretty) => {
+            let value = parse(&options, &file_path).unwrap_or_else(|e| {
+                eprintln!("{}", describe(&file_path, &e));
+                exit(1);
+            });
+            let options = SerializeOptions {
+                pretty: Some(pretty),
+                ..SerializeOptions::default()
+            };
+            write_output(&value, &options, None);
+        }
+        Command::Minify(output) => {
+            let value = parse(&options, &file_path).unwrap_or_else(|e| {
+                eprintln!("{}", describe(&file_path, &e));
+                exit(1);
+            });
+            write_output(&value, &SerializeOptions::default(), output.as_deref());
+        }
+        Command::ValidateLines => validate_lines(&options, &file_path),
+    }
+}
 
-    if file_path.is_none() {
-        eprintln!("error: please provide an file!");
-        exit(1);
+fn parse_args(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(Command, ParserOptions, String), String> {
+    let mut command = Command::Parse;
+    let mut options = ParserOptions::default();
+    let mut ndjson = false;
+    let mut file_path = None;
+
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), &mut command) {
+            ("validate", Command::Parse) if file_path.is_none() => command = Command::Validate,
+            ("pretty", Command::Parse) if file_path.is_none() => {
+                command = Command::Pretty(PrettyOptions::default())
+            }
+            ("minify", Command::Parse) if file_path.is_none() => command = Command::Minify(None),
+            ("--indent", Command::Pretty(pretty)) => match number_arg(&arg, args.next())? {
+                width if width > MAX_INDENT => {
+                    return Err(format!("--indent can be at most {}", MAX_INDENT))
+                }
+                width => pretty.indent = Indent::Spaces(width),
+            },
+            ("--tabs", Command::Pretty(pretty)) => pretty.indent = Indent::Tabs,
+            ("--width", Command::Pretty(pretty)) => {
+                pretty.max_width = number_arg(&arg, args.next())?
+            }
+            ("--json5", _) => options.dialect = Dialect::Json5,
+            ("--jsonc", _) => options.dialect = Dialect::Jsonc,
+            ("--ndjson", Command::Parse | Command::Validate) => ndjson = true,
+            (flag, _) if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            _ if file_path.is_none() => file_path = Some(arg),
+            (_, Command::Minify(output @ None)) => *output = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    let Some(file_path) = file_path else {
+        return Err("please provide an file!".to_string());
+    };
+    ndjson |= file_path.ends_with(".ndjson") || file_path.ends_with(".jsonl");
+    if ndjson && matches!(command, Command::Parse | Command::Validate) {
+        command = Command::ValidateLines;
+    }
+    Ok((command, options, file_path))
+}
+
+fn number_arg(flag: &str, value: Option<String>) -> Result<usize, String> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("{} needs a number", flag))
+}
+
+// "-" reads the document from stdin instead of a file
+fn parse(options: &ParserOptions, file_path: &str) -> Result<JsonValue, ParseError> {
+    if file_path == "-" {
+        options.parse_reader(io::stdin().lock())
+    } else {
+        options.parse_json(file_path.to_string())
     }
+}
 
-    let file_path = file_path.unwrap();
+fn validate(options: &ParserOptions, file_path: &str) -> Result<(), ParseError> {
+    if file_path == "-" {
+        options.validate_reader(io::stdin().lock())
+    } else {
+        options.validate_json(file_path.to_string())
+    }
+}
 
-    let result = parse_json(file_path);
+// Reports every bad line rather than stopping at the first one
+fn validate_lines(options: &ParserOptions, file_path: &str) {
+    let reader: Box<dyn Read> = if file_path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        match File::open(file_path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                println!("FAIL\n{}", describe(file_path, &e.into()));
+                exit(1);
+            }
+        }
+    };
 
-    if let Ok(r) = result {
-        println!("PASS\n{:?}", r);
-        return;
+    let errors: Vec<String> = options
+        .ndjson_reader(reader)
+        .continue_on_error(true)
+        .filter_map(Result::err)
+        .map(|e| describe(file_path, &e))
+        .collect();
+    if errors.is_empty() {
+        println!("PASS");
+    } else {
+        println!("FAIL\n{}", errors.join("\n"));
+        exit(1);
     }
+}
+
+// Only called once the input parsed, so invalid input never produces an output file
+fn write_output(value: &JsonValue, options: &SerializeOptions, output: Option<&str>) {
+    let result = match output {
+        Some(path) => File::create(path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            options.to_writer(value, &mut writer)?;
+            writer.flush()
+        }),
+        None => {
+            let mut writer = BufWriter::new(io::stdout().lock());
+            options
+                .to_writer(value, &mut writer)
+                .and_then(|()| writer.flush())
+        }
+    };
 
     if let Err(e) = result {
-        println!("FAIL\n{:?}", e.to_string());
+        eprintln!("error: failed to write output: {}", e);
+        exit(1);
+    }
+}
+
+fn describe(file_path: &str, e: &ParseError) -> String {
+    match e.location() {
+        Some(at) => {
+            let start = at.span.start;
+            let mut message = format!("{}:{}:{}: {}", file_path, start.line, start.column, e);
+            if !at.excerpt.is_empty() {
+                message += &format!("\n    {}", at.excerpt);
+            }
+            message
+        }
+        None => format!("{}: {}", file_path, e),
     }
 }
@@ -0,0 +1,344 @@
+use crate::parser::{drop_nested, is_container, JsonValue};
+use std::{collections::HashMap, fmt, mem, slice, vec};
+
+/// The members of a JSON object, in the order they were written, with a hash index on
+/// the keys so lookups don't scan every member.
+///
+/// A parsed object can hold the same key more than once (see
+/// [`DuplicateKeys`](crate::DuplicateKeys)). Iteration gives every copy, while lookups
+/// find the last one, the value [`DuplicateKeys::KeepLast`](crate::DuplicateKeys::KeepLast)
+/// would have kept.
+#[derive(Default)]
+pub struct Map {
+    members: Vec<(String, JsonValue)>,
+    // Position in `members` of the last member with each key
+    index: HashMap<String, usize>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Map {
+            members: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        let &i = self.index.get(key)?;
+        Some(&self.members[i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue> {
+        let &i = self.index.get(key)?;
+        Some(&mut self.members[i].1)
+    }
+
+    /// Sets the value for `key` and returns the one it replaced. A new key goes at the
+    /// end, an existing one keeps its place.
+    pub fn insert(&mut self, key: String, value: JsonValue) -> Option<JsonValue> {
+        match self.index.get(&key) {
+            Some(&i) => Some(mem::replace(&mut self.members[i].1, value)),
+            None => {
+                self.push(key, value);
+                None
+            }
+        }
+    }
+
+    /// Removes every member with `key` and returns the value lookups would have found.
+    /// The other members keep their order, so this takes time linear in the map's size.
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue> {
+        let last = self.index.remove(key)?;
+        let (_, removed) = self.members.remove(last);
+        self.shift_index(last);
+
+        // Earlier copies of the key, which only a parsed object can have
+        let mut end = last;
+        while let Some(i) = self.members[..end].iter().rposition(|(k, _)| k == key) {
+            self.members.remove(i);
+            self.shift_index(i);
+            end = i;
+        }
+        Some(removed)
+    }
+
+    /// The member for `key`, to look at or change in place without looking it up twice.
+    pub fn entry(&mut self, key: String) -> MapEntry<'_> {
+        match self.index.get(&key) {
+            Some(&index) => MapEntry::Occupied(OccupiedEntry { map: self, index }),
+            None => MapEntry::Vacant(VacantEntry { map: self, key }),
+        }
+    }
+
+    pub fn iter(&self) -> MapIter<'_> {
+        MapIter(self.members.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> MapIterMut<'_> {
+        MapIterMut(self.members.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &JsonValue> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut JsonValue> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    // Adds a member even if the key is already there, as the parser does for duplicates
+    pub(crate) fn push(&mut self, key: String, value: JsonValue) {
+        self.index.insert(key.clone(), self.members.len());
+        self.members.push((key, value));
+    }
+
+    // Moves the index back over a member taken out at `removed`
+    fn shift_index(&mut self, removed: usize) {
+        for i in self.index.values_mut() {
+            if *i > removed {
+                *i -= 1;
+            }
+        }
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (i, (key, _)) in self.members.iter().enumerate() {
+            self.index.insert(key.clone(), i);
+        }
+    }
+}
+
+// The derived drop would recurse once per nesting level
+impl Drop for Map {
+    fn drop(&mut self) {
+        if self.members.iter().any(|(_, value)| is_container(value)) {
+            let members = mem::take(&mut self.members);
+            drop_nested(members.into_iter().map(|(_, value)| value).collect());
+        }
+    }
+}
+
+/// Two maps are equal when they have the same members in the same order.
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.members == other.members
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Keeps every member, duplicate keys included.
+impl From<Vec<(String, JsonValue)>> for Map {
+    fn from(members: Vec<(String, JsonValue)>) -> Self {
+        let mut map = Map {
+            members,
+            index: HashMap::new(),
+        };
+        map.reindex();
+        map
+    }
+}
+
+/// Later values for a key replace earlier ones, like [`Map::insert`].
+impl FromIterator<(String, JsonValue)> for Map {
+    fn from_iter<I: IntoIterator<Item = (String, JsonValue)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl Extend<(String, JsonValue)> for Map {
+    fn extend<I: IntoIterator<Item = (String, JsonValue)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (String, JsonValue);
+    type IntoIter = vec::IntoIter<(String, JsonValue)>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        mem::take(&mut self.members).into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a String, &'a JsonValue);
+    type IntoIter = MapIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Map {
+    type Item = (&'a String, &'a mut JsonValue);
+    type IntoIter = MapIterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// The members of a [`Map`] in order, from [`Map::iter`].
+pub struct MapIter<'a>(slice::Iter<'a, (String, JsonValue)>);
+
+impl<'a> Iterator for MapIter<'a> {
+    type Item = (&'a String, &'a JsonValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for MapIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl ExactSizeIterator for MapIter<'_> {}
+
+/// The members of a [`Map`] in order with their values mutable, from [`Map::iter_mut`].
+pub struct MapIterMut<'a>(slice::IterMut<'a, (String, JsonValue)>);
+
+impl<'a> Iterator for MapIterMut<'a> {
+    type Item = (&'a String, &'a mut JsonValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for MapIterMut<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl ExactSizeIterator for MapIterMut<'_> {}
+
+/// A member of a [`Map`] that may or may not be there yet, from [`Map::entry`].
+pub enum MapEntry<'a> {
+    Occupied(OccupiedEntry<'a>),
+    Vacant(VacantEntry<'a>),
+}
+
+impl<'a> MapEntry<'a> {
+    pub fn key(&self) -> &str {
+        match self {
+            MapEntry::Occupied(entry) => entry.key(),
+            MapEntry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// The value, after inserting `default` if the key wasn't there.
+    pub fn or_insert(self, default: JsonValue) -> &'a mut JsonValue {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> JsonValue) -> &'a mut JsonValue {
+        match self {
+            MapEntry::Occupied(entry) => entry.into_mut(),
+            MapEntry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Runs `f` on the value if the key is there.
+    pub fn and_modify(mut self, f: impl FnOnce(&mut JsonValue)) -> Self {
+        if let MapEntry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+pub struct OccupiedEntry<'a> {
+    map: &'a mut Map,
+    index: usize,
+}
+
+impl<'a> OccupiedEntry<'a> {
+    pub fn key(&self) -> &str {
+        &self.map.members[self.index].0
+    }
+
+    pub fn get(&self) -> &JsonValue {
+        &self.map.members[self.index].1
+    }
+
+    pub fn get_mut(&mut self) -> &mut JsonValue {
+        &mut self.map.members[self.index].1
+    }
+
+    pub fn into_mut(self) -> &'a mut JsonValue {
+        &mut self.map.members[self.index].1
+    }
+
+    /// Replaces the value and returns the old one.
+    pub fn insert(&mut self, value: JsonValue) -> JsonValue {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Takes the member out of the map, like [`Map::remove`].
+    pub fn remove(self) -> JsonValue {
+        let key = self.key().to_string();
+        self.map
+            .remove(&key)
+            .expect("an occupied entry's key is in the map")
+    }
+}
+
+pub struct VacantEntry<'a> {
+    map: &'a mut Map,
+    key: String,
+}
+
+impl<'a> VacantEntry<'a> {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Adds the member at the end of the map.
+    pub fn insert(self, value: JsonValue) -> &'a mut JsonValue {
+        let index = self.map.members.len();
+        self.map.push(self.key, value);
+        &mut self.map.members[index].1
+    }
+}
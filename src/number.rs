@@ -0,0 +1,212 @@
+use std::fmt;
+
+/// A JSON number, kept exactly as it was written so no precision is lost until you
+/// ask for a specific type. Two numbers are equal when they have the same value,
+/// `1.50` and `15e-1` included.
+///
+/// JSON5 input can also give `Infinity`, `-Infinity` and `NaN`, other JSON5 forms like
+/// hex or `.5` are rewritten to plain JSON when they are read.
+#[derive(Clone)]
+pub struct Number {
+    lexeme: String,
+}
+
+impl Number {
+    pub(crate) fn from_lexeme(lexeme: String) -> Self {
+        Number { lexeme }
+    }
+
+    /// The number as written in the source.
+    pub fn as_str(&self) -> &str {
+        &self.lexeme
+    }
+
+    /// The closest `f64`, which may round for very long or very precise numbers.
+    pub fn as_f64(&self) -> f64 {
+        self.lexeme.parse().unwrap_or(f64::NAN)
+    }
+
+    /// False for the JSON5 `Infinity`, `-Infinity` and `NaN`.
+    pub fn is_finite(&self) -> bool {
+        !matches!(self.lexeme.trim_start_matches('-'), "Infinity" | "NaN")
+    }
+
+    /// The value as an `i128`, if it is a whole number that fits.
+    pub fn as_i128(&self) -> Option<i128> {
+        let decimal = self.as_decimal()?;
+        let exponent = u32::try_from(decimal.exponent).ok()?;
+        // Keep the sign on the digits so i128::MIN doesn't overflow on the way
+        let sign = if decimal.negative { "-" } else { "" };
+        format!("{}{}", sign, decimal.digits)
+            .parse::<i128>()
+            .ok()?
+            .checked_mul(10i128.checked_pow(exponent)?)
+    }
+
+    /// The value as an `i64`, if it is a whole number that fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128()?.try_into().ok()
+    }
+
+    /// The value as a `u64`, if it is a non-negative whole number that fits.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_i128()?.try_into().ok()
+    }
+
+    /// The exact value in base 10, however many digits it has. `None` if it isn't finite.
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        self.is_finite().then(|| Decimal::parse(&self.lexeme))
+    }
+
+    /// Builds a number from a float, `None` for NaN and infinities which JSON can't express.
+    pub fn from_f64(value: f64) -> Option<Number> {
+        value
+            .is_finite()
+            .then(|| Number::from_lexeme(value.to_string()))
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.as_decimal(), other.as_decimal()) {
+            (Some(a), Some(b)) => self.lexeme == other.lexeme || a == b,
+            // Like f64, so NaN isn't equal to itself
+            _ => self.as_f64() == other.as_f64(),
+        }
+    }
+}
+
+impl fmt::Debug for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Number({})", self.lexeme)
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lexeme)
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(value: $ty) -> Self {
+                    Number::from_lexeme(value.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// Converts hex digits, optionally after a `-`, to the same integer in base 10
+pub(crate) fn hex_to_decimal(hex: &str) -> String {
+    const BASE: u64 = 1_000_000_000;
+    let (sign, digits) = match hex.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", hex),
+    };
+
+    // Base 10^9 limbs, least significant first
+    let mut limbs: Vec<u64> = vec![0];
+    for digit in digits.chars().filter_map(|ch| ch.to_digit(16)) {
+        let mut carry = digit as u64;
+        for limb in &mut limbs {
+            let value = *limb * 16 + carry;
+            *limb = value % BASE;
+            carry = value / BASE;
+        }
+        if carry > 0 {
+            limbs.push(carry);
+        }
+    }
+
+    let mut result = sign.to_string();
+    let mut limbs = limbs.iter().rev();
+    result += &limbs.next().unwrap_or(&0).to_string();
+    for limb in limbs {
+        result += &format!("{:09}", limb);
+    }
+    result
+}
+
+/// An arbitrary precision decimal: `digits * 10^exponent`, negated if `negative`.
+/// Always normalized, so `digits` has no leading or trailing zeros (zero itself is `"0"`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decimal {
+    pub negative: bool,
+    pub digits: String,
+    pub exponent: i64,
+}
+
+impl Decimal {
+    // Expects a lexeme the lexer already accepted
+    fn parse(lexeme: &str) -> Decimal {
+        let (negative, rest) = match lexeme.as_bytes().first() {
+            Some(b'-') => (true, &lexeme[1..]),
+            Some(b'+') => (false, &lexeme[1..]),
+            _ => (false, lexeme),
+        };
+        let (mantissa, exponent) = match rest.find(['e', 'E']) {
+            Some(i) => (&rest[..i], &rest[i + 1..]),
+            None => (rest, ""),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+        let mut exponent = if exponent.is_empty() {
+            0
+        } else if exponent.starts_with('-') {
+            // Absurdly large exponents saturate rather than fail
+            exponent.parse::<i64>().unwrap_or(i64::MIN / 2)
+        } else {
+            exponent.parse::<i64>().unwrap_or(i64::MAX / 2)
+        };
+        exponent -= fraction.len() as i64;
+
+        let digits = format!("{}{}", integer, fraction);
+        let digits = digits.trim_start_matches('0');
+        let trimmed = digits.trim_end_matches('0');
+        exponent += (digits.len() - trimmed.len()) as i64;
+
+        if trimmed.is_empty() {
+            return Decimal {
+                negative: false,
+                digits: "0".to_string(),
+                exponent: 0,
+            };
+        }
+
+        Decimal {
+            negative,
+            digits: trimmed.to_string(),
+            exponent,
+        }
+    }
+}
+
+impl fmt::Display for Decimal {
+    // Plain notation like `-0.00125` for reasonable sizes, `1.25e-300` past that
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        let len = self.digits.len() as i64;
+        let point = len + self.exponent; // Where the decimal point goes, counted from the left
+        if self.exponent >= 0 && point <= 21 {
+            write!(f, "{}{}", self.digits, "0".repeat(self.exponent as usize))
+        } else if point > 0 && point <= 21 {
+            let (integer, fraction) = self.digits.split_at(point as usize);
+            write!(f, "{}.{}", integer, fraction)
+        } else if point <= 0 && point > -6 {
+            write!(f, "0.{}{}", "0".repeat(-point as usize), self.digits)
+        } else {
+            let (first, rest) = self.digits.split_at(1);
+            let dot = if rest.is_empty() { "" } else { "." };
+            write!(f, "{}{}{}e{}", first, dot, rest, point - 1)
+        }
+    }
+}
@@ -0,0 +1,87 @@
+/// What to do with a `\u` escape that is half of a UTF-16 surrogate pair with no other half.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoneSurrogates {
+    /// Reject the document.
+    #[default]
+    Error,
+    /// Swap the escape for U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+    /// Keep the surrogate. Rust strings can't hold one, so the string is read as a
+    /// [`JsonValue::Utf16String`](crate::JsonValue::Utf16String) instead, which the
+    /// serializer writes back as the same `\u` escape. Object keys still have to be valid
+    /// Unicode, a lone surrogate in one is an error.
+    Preserve,
+}
+
+/// What to do when an object has the same key more than once. RFC 8259 leaves it up to
+/// the parser, and consumers that pick different copies can be played against each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Keep every copy, in the order they were written.
+    #[default]
+    Allow,
+    /// Reject the document, pointing at both copies of the key.
+    Error,
+    /// Keep the first value and ignore the later ones.
+    KeepFirst,
+    /// Keep the last value, at the position of the first copy of the key, like
+    /// JavaScript's `JSON.parse`.
+    KeepLast,
+}
+
+/// Which flavour of JSON the input is written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// Plain RFC 8259 JSON.
+    #[default]
+    Json,
+    /// [JSON5](https://json5.org): comments, trailing commas, single quoted strings,
+    /// identifier keys, hex numbers, `Infinity`, `NaN` and decimal points at either end
+    /// of a number.
+    Json5,
+    /// JSON with comments, as in VS Code settings and `tsconfig.json`: `//` and `/* */`
+    /// comments and trailing commas, but otherwise plain JSON.
+    Jsonc,
+}
+
+impl Dialect {
+    pub(crate) fn allows_comments(self) -> bool {
+        matches!(self, Dialect::Json5 | Dialect::Jsonc)
+    }
+
+    pub(crate) fn allows_trailing_commas(self) -> bool {
+        matches!(self, Dialect::Json5 | Dialect::Jsonc)
+    }
+}
+
+/// Knobs for how strict the parser is. `ParserOptions::default()` is what the plain
+/// `parse_*` and `validate_*` functions use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserOptions {
+    pub dialect: Dialect,
+    pub lone_surrogates: LoneSurrogates,
+    pub duplicate_keys: DuplicateKeys,
+    /// Only accept an object or array as the top-level value, as the obsolete RFC 4627
+    /// did. Off by default, so `42` and `"hello"` are whole documents like RFC 8259 says.
+    pub require_container_root: bool,
+    /// How many arrays and objects a value may be nested in, `None` for no limit.
+    /// Defaults to [`DEFAULT_MAX_DEPTH`](ParserOptions::DEFAULT_MAX_DEPTH).
+    pub max_depth: Option<usize>,
+}
+
+impl ParserOptions {
+    /// Deep enough for any real document, shallow enough to stop `[[[[...` floods early.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            dialect: Dialect::default(),
+            lone_surrogates: LoneSurrogates::default(),
+            duplicate_keys: DuplicateKeys::default(),
+            require_container_root: false,
+            max_depth: Some(Self::DEFAULT_MAX_DEPTH),
+        }
+    }
+}
@@ -1,426 +1,844 @@
-use anyhow::{anyhow, Context, Result};
-use std::fs;
-
-const MAX_DEPTH: usize = 19; // Got this from fail18.json from json.org test suite for json parser
-
-#[derive(Debug, PartialEq)]
+use crate::array::Array;
+use crate::error::{Container, ParseError, Position, Span};
+use crate::lexer::{Lexer, SpannedToken, Token};
+use crate::map::{self, Map};
+use crate::number::Number;
+use crate::options::{Dialect, DuplicateKeys, ParserOptions};
+use std::{
+    collections::HashMap,
+    fmt::{self, Write as _},
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    mem,
+    ops::Index,
+    slice,
+};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+/// A parsed JSON value. Dropping, comparing, printing and serializing it all walk the tree
+/// without recursing, so values nested as deep as `max_depth: None` allows are safe to use.
 pub enum JsonValue {
-    Object(Vec<(String, JsonValue)>),
-    Array(Vec<JsonValue>),
+    Object(Map),
+    Array(Array),
     String(String),
-    Number(f64),
+    /// A string with an unpaired surrogate, which only
+    /// [`LoneSurrogates::Preserve`](crate::LoneSurrogates::Preserve) produces. It's kept as
+    /// the UTF-16 code units JSON strings are made of, since a `String` can't hold it.
+    Utf16String(Vec<u16>),
+    Number(Number),
     Bool(bool),
     Null,
 }
 
-#[derive(Debug)]
-enum Token {
-    CurlyOpen,
-    CurlyClose,
-    SquareOpen,
-    SquareClose,
-    Colon,
-    Comma,
-    String(String),
-    Number(f64),
-    Bool(bool),
-    Null,
+// Drops the values in `stack` and everything nested in them without recursing, by moving
+// the children of every container onto the stack. Used by the containers' own drops
+pub(crate) fn drop_nested(mut stack: Vec<JsonValue>) {
+    while let Some(value) = stack.pop() {
+        match value {
+            JsonValue::Object(object) => stack.extend(object.into_iter().map(|(_, value)| value)),
+            JsonValue::Array(mut array) => stack.append(&mut array),
+            _ => {}
+        }
+    }
 }
 
-// TODO: Reading a file to memory is not great
-// If the file is too big this cloud crash or be really slow
-// Try a stream or something later!!
-fn read_file(path: String) -> Result<String> {
-    fs::read_to_string(path).context("Falied to read File")
+pub(crate) fn is_container(value: &JsonValue) -> bool {
+    matches!(value, JsonValue::Object(_) | JsonValue::Array(_))
 }
 
-fn tokenize_unicode_sequence(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String> {
-    let mut result = String::new();
-
-    chars.next(); // Skip 'u'
-    let mut unicode_sequence = String::new();
-    for _ in 0..4 {
-        if let Some(&hex_digit) = chars.peek() {
-            if hex_digit.is_ascii_hexdigit() {
-                unicode_sequence.push(hex_digit);
-                chars.next();
-            } else {
-                return Err(anyhow!("Invalid Unicode escape sequence"));
-            }
-        } else {
-            return Err(anyhow!(
-                "Unexpected end of input in Unicode escape sequence"
-            ));
+// The members of an array or object still to be visited, for walking a tree with a stack
+// instead of recursing
+pub(crate) enum Members<'a> {
+    Object(map::MapIter<'a>),
+    Array(slice::Iter<'a, JsonValue>),
+}
+
+impl<'a> Members<'a> {
+    // None for values that aren't containers
+    pub(crate) fn of(value: &'a JsonValue) -> Option<Members<'a>> {
+        match value {
+            JsonValue::Object(object) => Some(Members::Object(object.iter())),
+            JsonValue::Array(array) => Some(Members::Array(array.iter())),
+            _ => None,
         }
     }
 
-    if let Ok(unicode_char) =
-        u16::from_str_radix(&unicode_sequence, 16).map(|u| char::from_u32(u as u32))
-    {
-        if let Some(c) = unicode_char {
-            result.push(c);
-        } else {
-            return Err(anyhow!("Invalid Unicode character"));
+    // The next member and, in an object, its key
+    pub(crate) fn next(&mut self) -> Option<(Option<&'a String>, &'a JsonValue)> {
+        match self {
+            Members::Object(members) => members.next().map(|(key, value)| (Some(key), value)),
+            Members::Array(elements) => elements.next().map(|value| (None, value)),
         }
-    } else {
-        return Err(anyhow!("Invalid Unicode escape sequence"));
     }
 
-    Ok(result)
+    // How many members are left
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Members::Object(members) => members.len(),
+            Members::Array(elements) => elements.len(),
+        }
+    }
+
+    pub(crate) fn brackets(&self) -> (char, char) {
+        match self {
+            Members::Object(_) => ('{', '}'),
+            Members::Array(_) => ('[', ']'),
+        }
+    }
 }
 
-fn tokenize_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String> {
-    let mut result = String::new();
-    chars.next(); // Skip opening (") quote
-
-    while let Some(&ch) = chars.peek() {
-        match ch {
-            '\\' => {
-                chars.next(); // Skip the backslash
-                if let Some(&escaped_char) = chars.peek() {
-                    match escaped_char {
-                        '"' => result.push('"'),
-                        '\\' => result.push('\\'),
-                        '/' => result.push('/'),
-                        'b' => result.push('\x08'), // Backspace rust don't like \b in char
-                        'f' => result.push('\x0C'), // Form feed rust don't like \f in char
-                        'n' => result.push('\n'),
-                        'r' => result.push('\r'),
-                        't' => result.push('\t'),
-                        'u' => {
-                            let unicode_sequence = tokenize_unicode_sequence(chars)?;
-                            result += &unicode_sequence;
+// Compares with a stack of pairs, like drop_nested
+impl PartialEq for JsonValue {
+    fn eq(&self, other: &Self) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (JsonValue::Object(a), JsonValue::Object(b)) if a.len() == b.len() => {
+                    for ((a_key, a), (b_key, b)) in a.iter().zip(b) {
+                        if a_key != b_key {
+                            return false;
                         }
-                        _ => return Err(anyhow!("Invalid escape sequence: \\{}", escaped_char)),
+                        stack.push((a, b));
                     }
-                    chars.next(); // Skip the escaped character
-                } else {
-                    return Err(anyhow!("Unexpected end of input after escape character"));
                 }
-            }
-            '"' => {
-                chars.next(); // Skip closing (") quote
-                break; // Closing quote found
-            }
-            _ if ch.is_whitespace() && ch != ' ' => {
-                return Err(anyhow!(
-                    "Invalid unescaped whitespace character in string: {}",
-                    ch
-                ));
-            }
-            _ => {
-                result.push(ch);
-                chars.next();
+                (JsonValue::Array(a), JsonValue::Array(b)) if a.len() == b.len() => {
+                    stack.extend(a.iter().zip(b.iter()))
+                }
+                (JsonValue::String(a), JsonValue::String(b)) if a == b => {}
+                (JsonValue::Utf16String(a), JsonValue::Utf16String(b)) if a == b => {}
+                (JsonValue::Number(a), JsonValue::Number(b)) if a == b => {}
+                (JsonValue::Bool(a), JsonValue::Bool(b)) if a == b => {}
+                (JsonValue::Null, JsonValue::Null) => {}
+                _ => return false,
             }
         }
+        true
     }
-
-    Ok(result)
 }
 
-fn tokenize_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<f64, anyhow::Error> {
-    let mut result = String::new();
-    let mut is_first_char = true;
-    let mut has_dot = false;
-
-    while let Some(&ch) = chars.peek() {
-        match ch {
-            '0'..='9' => {
-                if is_first_char && ch == '0' {
-                    chars.next(); // Consume the '0'
-                    if let Some(&next_ch) = chars.peek() {
-                        match next_ch {
-                            '.' => {
-                                // Handle 0.x numbers
-                                result.push('0');
+// Same output as the derive, `{:#?}` included, but with a stack instead of recursion
+impl fmt::Debug for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pretty = f.alternate();
+        let indent = |f: &mut fmt::Formatter<'_>, level: usize| -> fmt::Result {
+            for _ in 0..level {
+                f.write_str("    ")?;
+            }
+            Ok(())
+        };
+        // Closes the variant around a value at `level`
+        let close = |f: &mut fmt::Formatter<'_>, level: usize| -> fmt::Result {
+            if pretty {
+                f.write_str(",\n")?;
+                indent(f, level)?;
+            }
+            f.write_char(')')
+        };
+
+        // Open containers with their level and whether they have shown a member yet
+        let mut stack: Vec<(Members<'_>, usize, bool)> = Vec::new();
+        let mut next = Some((self, 0));
+        loop {
+            if let Some((value, level)) = next.take() {
+                let name = match value {
+                    JsonValue::Object(_) => "Object",
+                    JsonValue::Array(_) => "Array",
+                    JsonValue::String(_) => "String",
+                    JsonValue::Utf16String(_) => "Utf16String",
+                    JsonValue::Number(_) => "Number",
+                    JsonValue::Bool(_) => "Bool",
+                    JsonValue::Null => "Null",
+                };
+                f.write_str(name)?;
+                if !value.is_null() {
+                    f.write_char('(')?;
+                    if pretty {
+                        f.write_char('\n')?;
+                        indent(f, level + 1)?;
+                    }
+                    match Members::of(value) {
+                        Some(members) => {
+                            let (start, end) = members.brackets();
+                            f.write_char(start)?;
+                            if members.len() == 0 {
+                                f.write_char(end)?;
+                                close(f, level)?;
+                            } else {
+                                stack.push((members, level, false));
                             }
-                            '0'..='9' => return Err(anyhow!("Invalid number with leading zero")),
-                            _ => {
-                                result.push('0'); // Just 0
-                                break;
+                        }
+                        None => {
+                            match value {
+                                JsonValue::String(s) => write!(f, "{:?}", s)?,
+                                JsonValue::Utf16String(units) => write!(f, "{:?}", units)?,
+                                JsonValue::Number(n) => write!(f, "{:?}", n)?,
+                                JsonValue::Bool(b) => write!(f, "{:?}", b)?,
+                                _ => {}
                             }
+                            close(f, level)?;
                         }
-                    } else {
-                        result.push('0'); // Just 0
-                        break;
                     }
-                } else {
-                    result.push(ch);
-                    chars.next();
                 }
             }
-            '.' => {
-                if has_dot {
-                    return Err(anyhow!("Multiple decimal points in number"));
+
+            let Some((members, level, started)) = stack.last_mut() else {
+                return Ok(());
+            };
+            let level = *level;
+            let was_started = mem::replace(started, true);
+            match members.next() {
+                Some((key, value)) => {
+                    if pretty {
+                        if was_started {
+                            f.write_char(',')?;
+                        }
+                        f.write_char('\n')?;
+                        indent(f, level + 2)?;
+                    } else if was_started {
+                        f.write_str(", ")?;
+                    }
+                    if let Some(key) = key {
+                        write!(f, "{:?}: ", key)?;
+                    }
+                    next = Some((value, level + 2));
                 }
-                result.push(ch);
-                chars.next();
-                has_dot = true;
-            }
-            '-' | '+' if is_first_char => {
-                result.push(ch);
-                chars.next();
-            }
-            'e' | 'E' => {
-                result.push(ch);
-                chars.next();
-                // After 'e' or 'E', we should expect a digit or a sign
-                if let Some(&next_ch) = chars.peek() {
-                    if next_ch == '-' || next_ch == '+' || next_ch.is_digit(10) {
-                        result.push(next_ch);
-                        chars.next();
-                    } else {
-                        return Err(anyhow!("Invalid character after exponent"));
+                None => {
+                    let (_, end) = members.brackets();
+                    if pretty {
+                        f.write_str(",\n")?;
+                        indent(f, level + 1)?;
                     }
-                } else {
-                    return Err(anyhow!("Exponent without digits"));
+                    f.write_char(end)?;
+                    stack.pop();
+                    close(f, level)?;
                 }
             }
-            _ => break,
         }
-        is_first_char = false;
     }
+}
 
-    if let Some(ch) = result.chars().last() {
-        if matches!(ch, 'e' | 'E' | '.' | '-' | '+') {
-            return Err(anyhow!("Invalid number"));
-        }
+// What indexing gives for a missing key or element, so lookups can be chained
+static NULL: JsonValue = JsonValue::Null;
+
+impl JsonValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
     }
 
-    match result.to_lowercase().parse() {
-        Ok(number) => Ok(number),
-        Err(..) => Err(anyhow!("Number is invalid")),
+    pub fn is_bool(&self) -> bool {
+        matches!(self, JsonValue::Bool(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, JsonValue::Number(_))
     }
-}
 
-fn tokenize_bool(chars: &mut std::iter::Peekable<std::str::Chars>) -> bool {
-    let mut result = String::new();
+    /// True for [`Utf16String`](JsonValue::Utf16String) too, though `as_str` can't give it.
+    pub fn is_string(&self) -> bool {
+        matches!(self, JsonValue::String(_) | JsonValue::Utf16String(_))
+    }
 
-    while let Some(&ch) = chars.peek() {
-        match ch {
-            't' | 'r' | 'u' | 'e' | 'f' | 'a' | 'l' | 's' => {
-                result.push(ch);
-                chars.next();
-            }
-            _ => break,
+    pub fn is_array(&self) -> bool {
+        matches!(self, JsonValue::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, JsonValue::Object(_))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
         }
     }
 
-    result == "true"
-}
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            JsonValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
 
-fn tokenize_null(chars: &mut std::iter::Peekable<std::str::Chars>) {
-    let mut result = String::new();
+    /// The number as the closest `f64`, see [`Number::as_f64`].
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().map(Number::as_f64)
+    }
 
-    while let Some(&ch) = chars.peek() {
-        match ch {
-            'n' | 'u' | 'l' => {
-                result.push(ch);
-                chars.next();
-            }
-            _ => break,
+    /// The number as an `i64`, if it is a whole number that fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number()?.as_i64()
+    }
+
+    /// The number as a `u64`, if it is a non-negative whole number that fits.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number()?.as_u64()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
         }
     }
 
-    assert_eq!(result, "null");
-}
+    pub fn as_array(&self) -> Option<&Array> {
+        match self {
+            JsonValue::Array(array) => Some(array),
+            _ => None,
+        }
+    }
 
-fn tokenize(input: String) -> Result<Vec<Token>> {
-    if input.is_empty() {
-        return Err(anyhow!("Empty file"));
+    pub fn as_array_mut(&mut self) -> Option<&mut Array> {
+        match self {
+            JsonValue::Array(array) => Some(array),
+            _ => None,
+        }
     }
 
-    let mut tokens = Vec::new();
-    let mut chars = input.chars().peekable();
+    pub fn as_object(&self) -> Option<&Map> {
+        match self {
+            JsonValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
 
-    while let Some(&ch) = chars.peek() {
-        match ch {
-            '{' => {
-                tokens.push(Token::CurlyOpen);
-                chars.next();
-            }
-            '}' => {
-                tokens.push(Token::CurlyClose);
-                chars.next();
-            }
-            '[' => {
-                tokens.push(Token::SquareOpen);
-                chars.next();
-            }
-            ']' => {
-                tokens.push(Token::SquareClose);
-                chars.next();
-            }
-            ':' => {
-                tokens.push(Token::Colon);
-                chars.next();
-            }
-            ',' => {
-                tokens.push(Token::Comma);
-                chars.next();
-            }
-            '"' => {
-                tokens.push(Token::String(tokenize_string(&mut chars)?));
-            }
-            '0'..='9' | '-' => {
-                tokens.push(Token::Number(tokenize_number(&mut chars)?));
-            }
-            't' | 'f' => {
-                tokens.push(Token::Bool(tokenize_bool(&mut chars)));
-            }
-            'n' => {
-                tokenize_null(&mut chars);
-                tokens.push(Token::Null);
-            }
-            _ if ch.is_whitespace() => {
-                chars.next();
-            }
-            _ => return Err(anyhow!("Unexpected character: {}", ch)),
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
+        match self {
+            JsonValue::Object(object) => Some(object),
+            _ => None,
         }
     }
 
-    Ok(tokens)
+    /// The value for `key`, if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.as_object()?.get(key)
+    }
+
+    /// The element at `index`, if this is an array that long.
+    pub fn get_index(&self, index: usize) -> Option<&JsonValue> {
+        self.as_array()?.get(index)
+    }
 }
 
-fn parse_tokens(tokens: Vec<Token>) -> Result<JsonValue> {
-    let mut iter = tokens.iter().peekable();
-    let value = parse_value(&mut iter, 0)?;
+/// `value["key"]` is the member's value, or `Null` when `value` is not an object or has
+/// no such key, so `value["users"][0]["name"]` never panics.
+impl Index<&str> for JsonValue {
+    type Output = JsonValue;
 
-    // Check if there are any remaining tokens after the top-level value
-    if iter.peek().is_some() {
-        return Err(anyhow!("Extra tokens after top-level value"));
+    fn index(&self, key: &str) -> &JsonValue {
+        self.get(key).unwrap_or(&NULL)
     }
+}
 
-    match value {
-        JsonValue::Object(_) | JsonValue::Array(_) => Ok(value),
-        _ => Err(anyhow!(
-            "A JSON payload should be an object or array, not a string."
-        )),
+/// `value[i]` is the array element, or `Null` when `value` is not an array or is too short.
+impl Index<usize> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, index: usize) -> &JsonValue {
+        self.get_index(index).unwrap_or(&NULL)
     }
 }
 
-fn parse_value<'a, I>(tokens: &mut std::iter::Peekable<I>, depth: usize) -> Result<JsonValue>
-where
-    I: Iterator<Item = &'a Token>,
-{
-    if depth > MAX_DEPTH {
-        return Err(anyhow!("Exceeded maximum nesting depth"));
-    }
-
-    match tokens.peek() {
-        Some(Token::CurlyOpen) => parse_object(tokens, depth),
-        Some(Token::SquareOpen) => parse_array(tokens, depth),
-        Some(Token::String(_)) => {
-            if let Some(Token::String(s)) = tokens.next() {
-                Ok(JsonValue::String(s.clone()))
-            } else {
-                Err(anyhow!("Expected a string"))
-            }
+// Error for finding `token` where the grammar wants `expected`
+fn unexpected(token: &SpannedToken, expected: &'static str) -> ParseError {
+    match token.token {
+        Token::Eof => ParseError::UnexpectedEof {
+            expected,
+            at: token.span.into(),
+        },
+        _ => ParseError::UnexpectedToken {
+            expected,
+            at: token.span.into(),
+        },
+    }
+}
+
+// An array or object whose closing bracket hasn't been reached yet
+enum Partial {
+    Object(PartialObject),
+    Array(Vec<JsonValue>),
+}
+
+struct PartialObject {
+    members: Map,
+    // Key of the value being parsed
+    key: String,
+    policy: DuplicateKeys,
+    // Only needed to report both copies of a key, the other policies go by `members`
+    keys: Option<SeenKeys>,
+}
+
+impl PartialObject {
+    fn new(key: String, span: Span, policy: DuplicateKeys) -> Result<PartialObject> {
+        let mut object = PartialObject {
+            members: Map::new(),
+            key: String::new(),
+            policy,
+            keys: (policy == DuplicateKeys::Error).then(SeenKeys::default),
+        };
+        object.set_key(key, span)?;
+        Ok(object)
+    }
+
+    fn set_key(&mut self, key: String, span: Span) -> Result<()> {
+        if let Some(keys) = &mut self.keys {
+            keys.check(&key, span)?;
         }
-        Some(Token::Number(_)) => {
-            if let Some(Token::Number(n)) = tokens.next() {
-                Ok(JsonValue::Number(*n))
-            } else {
-                Err(anyhow!("Expected a number"))
+        self.key = key;
+        Ok(())
+    }
+
+    fn push(&mut self, value: JsonValue) {
+        let key = mem::take(&mut self.key);
+        match self.policy {
+            DuplicateKeys::KeepFirst if self.members.contains_key(&key) => {}
+            // Replaces an earlier value in place
+            DuplicateKeys::KeepLast => {
+                self.members.insert(key, value);
             }
+            _ => self.members.push(key, value),
         }
-        Some(Token::Bool(_)) => {
-            if let Some(Token::Bool(b)) = tokens.next() {
-                Ok(JsonValue::Bool(*b))
-            } else {
-                Err(anyhow!("Expected a boolean"))
+    }
+}
+
+// Where each key of one object was first seen, for `DuplicateKeys::Error`
+#[derive(Default)]
+struct SeenKeys {
+    first: HashMap<String, Position>,
+}
+
+impl SeenKeys {
+    fn check(&mut self, key: &str, span: Span) -> Result<()> {
+        match self.first.get(key) {
+            Some(&first) => Err(ParseError::DuplicateKey {
+                key: key.to_string(),
+                first,
+                at: span.into(),
+            }),
+            None => {
+                self.first.insert(key.to_string(), span.start);
+                Ok(())
             }
         }
-        Some(Token::Null) => {
-            tokens.next(); // Consume the Null token
-            Ok(JsonValue::Null)
-        }
-        _ => Err(anyhow!("Unexpected token")),
     }
 }
 
-fn parse_object<'a, I>(tokens: &mut std::iter::Peekable<I>, depth: usize) -> Result<JsonValue>
-where
-    I: Iterator<Item = &'a Token>,
-{
-    let mut object = Vec::new();
-    tokens.next(); // Consume the '{' (Open curly bracket)
-
-    loop {
-        match tokens.peek() {
-            Some(Token::CurlyClose) => {
-                tokens.next(); // Consume the '}'
-                break;
+pub(crate) fn parse_tokens<R: BufRead>(
+    tokens: &mut Lexer<R>,
+    options: &ParserOptions,
+) -> Result<JsonValue> {
+    let value = parse_document(tokens, options)?;
+
+    // Check if there are any remaining tokens after the top-level value
+    let token = tokens.peek_token()?;
+    if !matches!(token.token, Token::Eof) {
+        return Err(ParseError::TrailingCharacters {
+            at: token.span.into(),
+        });
+    }
+
+    Ok(value)
+}
+
+// Reads one top-level value and leaves whatever follows it to the caller.
+// Walks the input with an explicit stack instead of recursing, so deep nesting can't
+// overflow the call stack however high `max_depth` is set
+pub(crate) fn parse_document<R: BufRead>(
+    tokens: &mut Lexer<R>,
+    options: &ParserOptions,
+) -> Result<JsonValue> {
+    let mut stack: Vec<Partial> = Vec::new();
+    let start = tokens.peek_token()?.span;
+
+    let value = 'value: loop {
+        check_depth(tokens, options, stack.len())?;
+
+        let token = tokens.next_token()?;
+        let mut value = match token.token {
+            Token::CurlyOpen => {
+                let token = tokens.next_token()?;
+                match token.token {
+                    Token::CurlyClose => JsonValue::Object(Map::new()),
+                    _ => {
+                        let span = token.span;
+                        let key = object_key(token, options)?;
+                        stack.push(Partial::Object(PartialObject::new(
+                            key,
+                            span,
+                            options.duplicate_keys,
+                        )?));
+                        expect_colon(tokens)?;
+                        continue 'value;
+                    }
+                }
             }
-            Some(Token::String(_)) => {
-                if let Some(Token::String(key)) = tokens.next() {
-                    if let Some(Token::Colon) = tokens.next() {
-                        let value = parse_value(tokens, depth + 1)?;
-                        object.push((key.clone(), value));
-                        match tokens.peek() {
-                            Some(Token::Comma) => {
-                                tokens.next(); // Consume the ','
-                                if let Some(Token::CurlyClose) = tokens.peek() {
-                                    return Err(anyhow!("Trailing comma in object"));
+            Token::SquareOpen => {
+                let token = tokens.peek_token()?;
+                match token.token {
+                    Token::SquareClose => {
+                        tokens.next_token()?;
+                        JsonValue::Array(Array::new())
+                    }
+                    Token::Eof => return Err(unexpected(token, "value or ']'")),
+                    _ => {
+                        stack.push(Partial::Array(Vec::new()));
+                        continue 'value;
+                    }
+                }
+            }
+            Token::String(s) => JsonValue::String(s),
+            Token::Utf16String { units, .. } => JsonValue::Utf16String(units),
+            Token::Number(n) => JsonValue::Number(n),
+            Token::Bool(b) => JsonValue::Bool(b),
+            Token::Null => JsonValue::Null,
+            _ => return Err(unexpected(&token, "a value")),
+        };
+
+        // A value just ended, add it to its container and close every container that ends with it
+        loop {
+            let partial = match stack.last_mut() {
+                Some(partial) => partial,
+                None => break 'value value,
+            };
+            let token = tokens.next_token()?;
+            match partial {
+                Partial::Object(object) => {
+                    object.push(value);
+                    match token.token {
+                        Token::Comma => {
+                            let next = tokens.next_token()?;
+                            match next.token {
+                                Token::CurlyClose if options.dialect.allows_trailing_commas() => {}
+                                Token::CurlyClose => {
+                                    return Err(ParseError::TrailingComma {
+                                        container: Container::Object,
+                                        at: token.span.into(),
+                                    })
+                                }
+                                _ => {
+                                    let span = next.span;
+                                    object.set_key(object_key(next, options)?, span)?;
+                                    expect_colon(tokens)?;
+                                    continue 'value;
                                 }
                             }
-                            Some(Token::CurlyClose) => {
-                                tokens.next(); // Consume the '}'
-                                break;
+                        }
+                        Token::CurlyClose => {}
+                        _ => return Err(unexpected(&token, "',' or '}' after object value")),
+                    }
+                }
+                Partial::Array(array) => {
+                    array.push(value);
+                    match token.token {
+                        Token::Comma => {
+                            if !matches!(tokens.peek_token()?.token, Token::SquareClose) {
+                                continue 'value;
+                            }
+                            if !options.dialect.allows_trailing_commas() {
+                                return Err(ParseError::TrailingComma {
+                                    container: Container::Array,
+                                    at: token.span.into(),
+                                });
                             }
-                            _ => return Err(anyhow!("Expected ',' or '}}' after object value")),
+                            tokens.next_token()?;
                         }
-                    } else {
-                        return Err(anyhow!("Expected ':' after key in object"));
+                        Token::SquareClose => {}
+                        _ => return Err(unexpected(&token, "',' or ']'")),
                     }
                 }
             }
-            _ => return Err(anyhow!("Expected string key or '}}' in object")),
+
+            value = match stack.pop() {
+                Some(Partial::Object(object)) => JsonValue::Object(object.members),
+                Some(Partial::Array(array)) => JsonValue::Array(array.into()),
+                None => unreachable!("a container was just closed"),
+            };
         }
+    };
+
+    match value {
+        JsonValue::Object(_) | JsonValue::Array(_) => Ok(value),
+        _ if options.require_container_root => {
+            Err(ParseError::NonContainerRoot { at: start.into() })
+        }
+        _ => Ok(value),
     }
+}
 
-    Ok(JsonValue::Object(object))
+// `depth` is how many containers the next value is nested in
+fn check_depth<R: BufRead>(
+    tokens: &mut Lexer<R>,
+    options: &ParserOptions,
+    depth: usize,
+) -> Result<()> {
+    match options.max_depth {
+        Some(limit) if depth > limit => Err(ParseError::DepthExceeded {
+            limit,
+            at: tokens.peek_token()?.span.into(),
+        }),
+        _ => Ok(()),
+    }
 }
 
-fn parse_array<'a, I>(tokens: &mut std::iter::Peekable<I>, depth: usize) -> Result<JsonValue>
-where
-    I: Iterator<Item = &'a Token>,
-{
-    let mut array = Vec::new();
-    tokens.next(); // Consume the '[' (Open bracket)
-
-    loop {
-        match tokens.peek() {
-            Some(Token::SquareClose) => {
-                tokens.next(); // Consume the ']' (Close bracket) end of array
-                break;
+// Same grammar as parse_tokens, but keeps nothing
+fn validate_tokens<R: BufRead>(tokens: &mut Lexer<R>, options: &ParserOptions) -> Result<()> {
+    let mut stack: Vec<Container> = Vec::new();
+    // One per open object. Only an error policy can make a duplicate key invalid
+    let mut keys: Vec<Option<SeenKeys>> = Vec::new();
+    let track_keys = options.duplicate_keys == DuplicateKeys::Error;
+    let root = tokens.peek_token()?.span;
+    let mut is_container_root = false;
+
+    'value: loop {
+        check_depth(tokens, options, stack.len())?;
+
+        let token = tokens.next_token()?;
+        match token.token {
+            Token::CurlyOpen => {
+                is_container_root |= stack.is_empty();
+                let token = tokens.next_token()?;
+                match token.token {
+                    Token::CurlyClose => {}
+                    _ => {
+                        let span = token.span;
+                        let key = object_key(token, options)?;
+                        let mut seen = track_keys.then(SeenKeys::default);
+                        if let Some(seen) = &mut seen {
+                            seen.check(&key, span)?;
+                        }
+                        stack.push(Container::Object);
+                        keys.push(seen);
+                        expect_colon(tokens)?;
+                        continue 'value;
+                    }
+                }
+            }
+            Token::SquareOpen => {
+                is_container_root |= stack.is_empty();
+                let token = tokens.peek_token()?;
+                match token.token {
+                    Token::SquareClose => {
+                        tokens.next_token()?;
+                    }
+                    Token::Eof => return Err(unexpected(token, "value or ']'")),
+                    _ => {
+                        stack.push(Container::Array);
+                        continue 'value;
+                    }
+                }
             }
-            Some(_) => {
-                let value = parse_value(tokens, depth + 1)?;
-                array.push(value);
-                match tokens.peek() {
-                    Some(Token::Comma) => {
-                        tokens.next(); // Consume the ',' (Comma)
-                        if let Some(Token::SquareClose) = tokens.peek() {
-                            return Err(anyhow!("Trailing comma in array"));
+            Token::String(_)
+            | Token::Utf16String { .. }
+            | Token::Number(_)
+            | Token::Bool(_)
+            | Token::Null => {}
+            _ => return Err(unexpected(&token, "a value")),
+        }
+
+        // A value just ended, close every container that ends with it
+        while let Some(&container) = stack.last() {
+            let token = tokens.next_token()?;
+            match (container, &token.token) {
+                (Container::Object, Token::Comma) => {
+                    let key = tokens.next_token()?;
+                    match key.token {
+                        Token::CurlyClose if options.dialect.allows_trailing_commas() => {
+                            stack.pop();
+                            keys.pop();
+                        }
+                        Token::CurlyClose => {
+                            return Err(ParseError::TrailingComma {
+                                container,
+                                at: token.span.into(),
+                            })
+                        }
+                        _ => {
+                            let span = key.span;
+                            let key = object_key(key, options)?;
+                            if let Some(Some(seen)) = keys.last_mut() {
+                                seen.check(&key, span)?;
+                            }
+                            expect_colon(tokens)?;
+                            continue 'value;
                         }
                     }
-                    Some(Token::SquareClose) => {
-                        tokens.next(); // Consume the ']' (Clase bracket) end of array
-                        break;
+                }
+                (Container::Array, Token::Comma) => {
+                    if !matches!(tokens.peek_token()?.token, Token::SquareClose) {
+                        continue 'value;
                     }
-                    _ => return Err(anyhow!("Expected ',' or ']'")),
-                };
+                    if !options.dialect.allows_trailing_commas() {
+                        return Err(ParseError::TrailingComma {
+                            container,
+                            at: token.span.into(),
+                        });
+                    }
+                    tokens.next_token()?;
+                    stack.pop();
+                }
+                (Container::Object, Token::CurlyClose) => {
+                    stack.pop();
+                    keys.pop();
+                }
+                (Container::Array, Token::SquareClose) => {
+                    stack.pop();
+                }
+                (Container::Object, _) => {
+                    return Err(unexpected(&token, "',' or '}' after object value"))
+                }
+                (Container::Array, _) => return Err(unexpected(&token, "',' or ']'")),
             }
-            _ => return Err(anyhow!("Expected value or ]")),
+        }
+        break;
+    }
+
+    if options.require_container_root && !is_container_root {
+        return Err(ParseError::NonContainerRoot { at: root.into() });
+    }
+
+    // Check if there are any remaining tokens after the top-level value
+    let token = tokens.peek_token()?;
+    if !matches!(token.token, Token::Eof) {
+        return Err(ParseError::TrailingCharacters {
+            at: token.span.into(),
+        });
+    }
+
+    Ok(())
+}
+
+// Object keys are strings, or in JSON5 any identifier, including the ones the lexer
+// reads as literals
+fn object_key(token: SpannedToken, options: &ParserOptions) -> Result<String> {
+    let is_json5 = options.dialect == Dialect::Json5;
+    match token.token {
+        Token::String(key) | Token::Identifier(key) => Ok(key),
+        // Map keys are `String`s, so a lone surrogate can't be kept there
+        Token::Utf16String {
+            lone: (code, span), ..
+        } => Err(ParseError::LoneSurrogate {
+            code,
+            at: span.into(),
+        }),
+        Token::Bool(b) if is_json5 => Ok(b.to_string()),
+        Token::Null if is_json5 => Ok("null".to_string()),
+        Token::Number(n) if is_json5 && n.as_str().starts_with(char::is_alphabetic) => {
+            Ok(n.as_str().to_string())
+        }
+        _ => Err(unexpected(&token, "string key or '}' in object")),
+    }
+}
+
+fn expect_colon<R: BufRead>(tokens: &mut Lexer<R>) -> Result<()> {
+    let token = tokens.next_token()?;
+    match token.token {
+        Token::Colon => Ok(()),
+        _ => Err(unexpected(&token, "':' after key in object")),
+    }
+}
+
+impl ParserOptions {
+    fn parse<R: BufRead>(&self, reader: R) -> Result<JsonValue> {
+        let mut tokens = Lexer::new(reader, *self);
+        parse_tokens(&mut tokens, self).map_err(|e| tokens.with_excerpt(e))
+    }
+
+    // Parses a document that starts at `start` in some bigger input
+    pub(crate) fn parse_at<R: BufRead>(&self, reader: R, start: Position) -> Result<JsonValue> {
+        let mut tokens = Lexer::starting_at(reader, *self, start);
+        parse_tokens(&mut tokens, self).map_err(|e| tokens.with_excerpt(e))
+    }
+
+    fn validate<R: BufRead>(&self, reader: R) -> Result<()> {
+        // Keys have to be kept to be compared
+        let mut tokens = match self.duplicate_keys {
+            DuplicateKeys::Error => Lexer::new(reader, *self),
+            _ => Lexer::without_values(reader, *self),
         };
+        validate_tokens(&mut tokens, self).map_err(|e| tokens.with_excerpt(e))
+    }
+
+    /// Like [`parse_json`], with these options.
+    pub fn parse_json(&self, path: String) -> Result<JsonValue> {
+        self.parse(BufReader::new(File::open(path)?))
     }
 
-    Ok(JsonValue::Array(array))
+    /// Like [`parse_str`], with these options.
+    pub fn parse_str(&self, input: &str) -> Result<JsonValue> {
+        self.parse(input.as_bytes())
+    }
+
+    /// Like [`parse_slice`], with these options.
+    pub fn parse_slice(&self, input: &[u8]) -> Result<JsonValue> {
+        self.parse(input)
+    }
+
+    /// Like [`parse_reader`], with these options.
+    pub fn parse_reader(&self, reader: impl Read) -> Result<JsonValue> {
+        self.parse(BufReader::new(reader))
+    }
+
+    /// Like [`validate_json`], with these options.
+    pub fn validate_json(&self, path: String) -> Result<()> {
+        self.validate(BufReader::new(File::open(path)?))
+    }
+
+    /// Like [`validate_str`], with these options.
+    pub fn validate_str(&self, input: &str) -> Result<()> {
+        self.validate(input.as_bytes())
+    }
+
+    /// Like [`validate_slice`], with these options.
+    pub fn validate_slice(&self, input: &[u8]) -> Result<()> {
+        self.validate(input)
+    }
+
+    /// Like [`validate_reader`], with these options.
+    pub fn validate_reader(&self, reader: impl Read) -> Result<()> {
+        self.validate(BufReader::new(reader))
+    }
 }
 
+/// Parses the JSON document stored in the file at `path`.
 pub fn parse_json(path: String) -> Result<JsonValue> {
-    let input = read_file(path)?;
-    let tokens = tokenize(input)?;
-    parse_tokens(tokens)
+    ParserOptions::default().parse_json(path)
+}
+
+/// Parses a JSON document held in memory.
+pub fn parse_str(input: &str) -> Result<JsonValue> {
+    ParserOptions::default().parse_str(input)
+}
+
+/// Parses a JSON document from raw bytes, which must be valid UTF-8.
+pub fn parse_slice(input: &[u8]) -> Result<JsonValue> {
+    ParserOptions::default().parse_slice(input)
+}
+
+/// Parses a JSON document from anything readable, like stdin or a socket.
+/// The input is read in chunks as parsing goes, it is never loaded whole.
+pub fn parse_reader(reader: impl Read) -> Result<JsonValue> {
+    ParserOptions::default().parse_reader(reader)
+}
+
+/// Checks that the file at `path` holds valid JSON without building a [`JsonValue`].
+/// Memory use stays flat no matter how big the file is, apart from one entry per nesting level.
+pub fn validate_json(path: String) -> Result<()> {
+    ParserOptions::default().validate_json(path)
+}
+
+/// Checks that `input` is valid JSON without building a [`JsonValue`].
+pub fn validate_str(input: &str) -> Result<()> {
+    ParserOptions::default().validate_str(input)
+}
+
+/// Checks that `input` is valid UTF-8 encoded JSON without building a [`JsonValue`].
+pub fn validate_slice(input: &[u8]) -> Result<()> {
+    ParserOptions::default().validate_slice(input)
+}
+
+/// Checks that everything `reader` produces is valid JSON without building a [`JsonValue`].
+pub fn validate_reader(reader: impl Read) -> Result<()> {
+    ParserOptions::default().validate_reader(reader)
 }
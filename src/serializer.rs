@@ -0,0 +1,375 @@
+use crate::parser::{JsonValue, Members};
+use std::{fmt, io, mem};
+
+/// Knobs for turning a [`JsonValue`] back into text. `SerializeOptions::default()` is
+/// what `Display` and [`JsonValue::to_writer`] use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// Write everything outside ASCII as `\u` escapes, for consumers that can't take UTF-8.
+    pub escape_non_ascii: bool,
+    /// Spread the output over indented lines. `None` writes compact JSON.
+    pub pretty: Option<PrettyOptions>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Spaces(usize),
+    /// One tab per level, counted as four columns when checking `max_width`.
+    Tabs,
+}
+
+const TAB_WIDTH: usize = 4;
+
+/// Layout of pretty printed output, the default matches what most editors do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrettyOptions {
+    pub indent: Indent,
+    /// Arrays and objects that fit on one line within this many columns are kept on one
+    /// line, like `[1, 2, 3]`. Zero puts every element on its own line.
+    pub max_width: usize,
+    pub space_after_colon: bool,
+    pub trailing_newline: bool,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        PrettyOptions {
+            indent: Indent::Spaces(2),
+            max_width: 80,
+            space_after_colon: true,
+            trailing_newline: true,
+        }
+    }
+}
+
+impl SerializeOptions {
+    /// Serializes `value` into a new string.
+    pub fn to_string(&self, value: &JsonValue) -> String {
+        let mut out = String::new();
+        write_document(&mut out, value, self).expect("writing to a String can't fail");
+        out
+    }
+
+    /// Serializes `value` straight into `writer`, without building the whole text first.
+    /// Output goes out in many small writes, so wrap files and sockets in a `BufWriter`.
+    pub fn to_writer(&self, value: &JsonValue, writer: impl io::Write) -> io::Result<()> {
+        let mut adapter = IoAdapter {
+            writer,
+            error: None,
+        };
+        match write_document(&mut adapter, value, self) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(adapter
+                .error
+                .unwrap_or_else(|| io::Error::other("formatting error"))),
+        }
+    }
+}
+
+impl JsonValue {
+    /// Writes the value as compact JSON into `writer`.
+    pub fn to_writer(&self, writer: impl io::Write) -> io::Result<()> {
+        SerializeOptions::default().to_writer(self, writer)
+    }
+
+    /// The value as JSON laid out with the default [`PrettyOptions`].
+    pub fn to_pretty_string(&self) -> String {
+        SerializeOptions {
+            pretty: Some(PrettyOptions::default()),
+            ..SerializeOptions::default()
+        }
+        .to_string(self)
+    }
+}
+
+/// `to_string()` gives compact JSON with no insignificant whitespace, and the alternate
+/// form `{:#}` pretty prints it with the default [`PrettyOptions`] (minus the trailing newline).
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = SerializeOptions::default();
+        if f.alternate() {
+            let pretty = PrettyOptions::default();
+            write_pretty(f, self, &options, &pretty)
+        } else {
+            write_value(f, self, &options, COMPACT)
+        }
+    }
+}
+
+// Lets the fmt::Write based serializer target an io::Write, keeping the real error around
+struct IoAdapter<W> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+// Counts the characters written and gives up once there are more than `limit`
+struct WidthCounter {
+    width: usize,
+    limit: usize,
+}
+
+impl fmt::Write for WidthCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.width += s.chars().count();
+        if self.width > self.limit {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+/// What goes between elements and between keys and values when a container is on one line.
+#[derive(Clone, Copy)]
+struct Separators {
+    comma: &'static str,
+    colon: &'static str,
+}
+
+const COMPACT: Separators = Separators {
+    comma: ",",
+    colon: ":",
+};
+
+fn write_document(
+    out: &mut impl fmt::Write,
+    value: &JsonValue,
+    options: &SerializeOptions,
+) -> fmt::Result {
+    match &options.pretty {
+        Some(pretty) => {
+            write_pretty(out, value, options, pretty)?;
+            if pretty.trailing_newline {
+                out.write_char('\n')?;
+            }
+            Ok(())
+        }
+        None => write_value(out, value, options, COMPACT),
+    }
+}
+
+fn write_indent(out: &mut impl fmt::Write, pretty: &PrettyOptions, level: usize) -> fmt::Result {
+    const SPACES: &str = "                                                                ";
+    match pretty.indent {
+        Indent::Spaces(width) => {
+            // In chunks, as a single `{:width$}` panics past u16::MAX columns
+            let mut left = width.saturating_mul(level);
+            while left > 0 {
+                let chunk = left.min(SPACES.len());
+                out.write_str(&SPACES[..chunk])?;
+                left -= chunk;
+            }
+            Ok(())
+        }
+        Indent::Tabs => (0..level).try_for_each(|_| out.write_char('\t')),
+    }
+}
+
+fn indent_width(pretty: &PrettyOptions, level: usize) -> usize {
+    match pretty.indent {
+        Indent::Spaces(width) => width.saturating_mul(level),
+        Indent::Tabs => TAB_WIDTH.saturating_mul(level),
+    }
+}
+
+// Open containers keep their remaining members on a stack rather than the call stack,
+// so values of any depth can be written
+fn write_pretty(
+    out: &mut impl fmt::Write,
+    value: &JsonValue,
+    options: &SerializeOptions,
+    pretty: &PrettyOptions,
+) -> fmt::Result {
+    let separators = Separators {
+        comma: ", ",
+        colon: if pretty.space_after_colon { ": " } else { ":" },
+    };
+
+    // Containers spread over lines, with whether they have written a member yet
+    let mut stack: Vec<(Members<'_>, bool)> = Vec::new();
+    // `column` is where the next value starts on its line, `trailer` how many characters
+    // follow it there (the comma after an element)
+    let mut next = Some((value, 0, 0));
+    loop {
+        if let Some((value, column, trailer)) = next.take() {
+            let mut counter = WidthCounter {
+                width: 0,
+                limit: pretty.max_width.saturating_sub(column + trailer),
+            };
+            match Members::of(value) {
+                Some(members)
+                    if members.len() > 0
+                        && write_value(&mut counter, value, options, separators).is_err() =>
+                {
+                    out.write_char(members.brackets().0)?;
+                    stack.push((members, false));
+                }
+                _ => write_value(out, value, options, separators)?,
+            }
+        }
+
+        let level = stack.len();
+        let Some((members, started)) = stack.last_mut() else {
+            return Ok(());
+        };
+        match members.next() {
+            Some((key, value)) => {
+                if mem::replace(started, true) {
+                    out.write_char(',')?;
+                }
+                out.write_char('\n')?;
+                write_indent(out, pretty, level)?;
+                let mut column = indent_width(pretty, level);
+                if let Some(key) = key {
+                    let mut key_width = WidthCounter {
+                        width: 0,
+                        limit: usize::MAX,
+                    };
+                    write_string(&mut key_width, key, options)?;
+                    write_string(out, key, options)?;
+                    out.write_str(separators.colon)?;
+                    column += key_width.width + separators.colon.len();
+                }
+                next = Some((value, column, (members.len() > 0) as usize));
+            }
+            None => {
+                let (_, end) = members.brackets();
+                stack.pop();
+                out.write_char('\n')?;
+                write_indent(out, pretty, level - 1)?;
+                out.write_char(end)?;
+            }
+        }
+    }
+}
+
+// Walks the tree with a stack like write_pretty, everything on one line
+fn write_value(
+    out: &mut impl fmt::Write,
+    value: &JsonValue,
+    options: &SerializeOptions,
+    separators: Separators,
+) -> fmt::Result {
+    // Open containers with whether they have written a member yet
+    let mut stack: Vec<(Members<'_>, bool)> = Vec::new();
+    let mut next = Some(value);
+    loop {
+        if let Some(value) = next.take() {
+            match Members::of(value) {
+                Some(members) => {
+                    out.write_char(members.brackets().0)?;
+                    stack.push((members, false));
+                }
+                None => write_scalar(out, value, options)?,
+            }
+        }
+
+        let Some((members, started)) = stack.last_mut() else {
+            return Ok(());
+        };
+        match members.next() {
+            Some((key, value)) => {
+                if mem::replace(started, true) {
+                    out.write_str(separators.comma)?;
+                }
+                if let Some(key) = key {
+                    write_string(out, key, options)?;
+                    out.write_str(separators.colon)?;
+                }
+                next = Some(value);
+            }
+            None => {
+                out.write_char(members.brackets().1)?;
+                stack.pop();
+            }
+        }
+    }
+}
+
+fn write_scalar(
+    out: &mut impl fmt::Write,
+    value: &JsonValue,
+    options: &SerializeOptions,
+) -> fmt::Result {
+    match value {
+        JsonValue::String(s) => write_string(out, s, options),
+        JsonValue::Utf16String(units) => write_utf16_string(out, units, options),
+        JsonValue::Number(n) if n.is_finite() => write!(out, "{}", n),
+        // JSON has no Infinity or NaN, write them the way JavaScript's JSON.stringify does
+        JsonValue::Number(_) => out.write_str("null"),
+        JsonValue::Bool(b) => write!(out, "{}", b),
+        JsonValue::Null => out.write_str("null"),
+        JsonValue::Object(_) | JsonValue::Array(_) => {
+            unreachable!("containers are walked by the caller")
+        }
+    }
+}
+
+fn write_string(out: &mut impl fmt::Write, s: &str, options: &SerializeOptions) -> fmt::Result {
+    out.write_char('"')?;
+    write_escaped(out, s, options)?;
+    out.write_char('"')
+}
+
+// Lone surrogates go back out as the `\u` escapes they were read from
+fn write_utf16_string(
+    out: &mut impl fmt::Write,
+    units: &[u16],
+    options: &SerializeOptions,
+) -> fmt::Result {
+    out.write_char('"')?;
+    let mut text = String::new();
+    for unit in char::decode_utf16(units.iter().copied()) {
+        match unit {
+            Ok(ch) => text.push(ch),
+            Err(e) => {
+                write_escaped(out, &text, options)?;
+                text.clear();
+                write!(out, "\\u{:04x}", e.unpaired_surrogate())?;
+            }
+        }
+    }
+    write_escaped(out, &text, options)?;
+    out.write_char('"')
+}
+
+fn write_escaped(out: &mut impl fmt::Write, s: &str, options: &SerializeOptions) -> fmt::Result {
+    // Copy runs of characters that need no escaping in one go
+    let mut run_start = 0;
+    for (i, ch) in s.char_indices() {
+        let needs_escape = matches!(ch, '"' | '\\' | '\0'..='\x1F')
+            || (options.escape_non_ascii && !ch.is_ascii());
+        if !needs_escape {
+            continue;
+        }
+
+        out.write_str(&s[run_start..i])?;
+        run_start = i + ch.len_utf8();
+        match ch {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\x08' => out.write_str("\\b")?,
+            '\x0C' => out.write_str("\\f")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            _ => {
+                // Characters outside the BMP become a UTF-16 surrogate pair
+                let mut units = [0u16; 2];
+                for unit in ch.encode_utf16(&mut units) {
+                    write!(out, "\\u{:04x}", unit)?;
+                }
+            }
+        }
+    }
+
+    out.write_str(&s[run_start..])
+}
@@ -0,0 +1,192 @@
+use crate::error::{ParseError, Position};
+use crate::lexer::{Lexer, Token};
+use crate::options::ParserOptions;
+use crate::parser::{parse_document, parse_tokens, JsonValue};
+use std::io::{BufRead, BufReader, Read};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+/// Reads newline-delimited JSON (NDJSON, also called JSON Lines): one document per line.
+/// Blank lines are skipped. Errors point at their line and column in the whole input.
+///
+/// By default the iterator stops after the first error, use
+/// [`continue_on_error`](NdjsonReader::continue_on_error) to get every line's result.
+pub struct NdjsonReader<R> {
+    reader: R,
+    options: ParserOptions,
+    position: Position,
+    continue_on_error: bool,
+    done: bool,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    fn new(reader: R, options: ParserOptions) -> Self {
+        NdjsonReader {
+            reader,
+            options,
+            position: Position::default(),
+            continue_on_error: false,
+            done: false,
+            line: Vec::new(),
+        }
+    }
+
+    /// Keep reading after a line that fails to parse. Errors reading the input itself
+    /// still end the iteration.
+    pub fn continue_on_error(mut self, continue_on_error: bool) -> Self {
+        self.continue_on_error = continue_on_error;
+        self
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = Result<JsonValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.line.clear();
+            let start = self.position;
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => self.done = true,
+                Ok(_) => {
+                    for &byte in &self.line {
+                        self.position.advance(byte);
+                    }
+                    if self.line.iter().all(u8::is_ascii_whitespace) {
+                        continue;
+                    }
+
+                    // Without the terminator, so a truncated line ends on its own line
+                    let line = self.line.strip_suffix(b"\n").unwrap_or(&self.line);
+                    let line = line.strip_suffix(b"\r").unwrap_or(line);
+                    let result = self.options.parse_at(line, start);
+                    self.done = result.is_err() && !self.continue_on_error;
+                    return Some(result);
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// How the documents read by a [`DocumentReader`] are separated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Values written back to back, like `{}{}[]`, with optional whitespace between them.
+    /// Two numbers in a row need whitespace, `12` is a single number.
+    Concatenated,
+    /// An RFC 7464 JSON text sequence, where every document starts with a record
+    /// separator (0x1E). Empty records are skipped.
+    RecordSeparated,
+}
+
+/// Reads a stream of top-level JSON values, see [`Framing`] for how they may be separated.
+/// Errors point at their line and column in the whole input.
+///
+/// With [`Framing::RecordSeparated`] a record that fails to parse is reported and the
+/// reader moves on to the next one. Concatenated values have nothing to resync on, so
+/// the iteration ends after the first error there.
+pub struct DocumentReader<R> {
+    tokens: Lexer<R>,
+    options: ParserOptions,
+    framing: Framing,
+    // The current record was already read, so the next call has to skip to the one after it
+    record_read: bool,
+    done: bool,
+}
+
+impl<R: BufRead> DocumentReader<R> {
+    fn new(reader: R, options: ParserOptions, framing: Framing) -> Self {
+        let tokens = match framing {
+            Framing::Concatenated => Lexer::new(reader, options),
+            Framing::RecordSeparated => Lexer::record_separated(reader, options),
+        };
+        DocumentReader {
+            tokens,
+            options,
+            framing,
+            record_read: false,
+            done: false,
+        }
+    }
+
+    fn next_concatenated(&mut self) -> Option<Result<JsonValue>> {
+        let result = match self.tokens.peek_token() {
+            Ok(token) if matches!(token.token, Token::Eof) => return None,
+            Err(ParseError::Empty) => return None,
+            Ok(_) => parse_document(&mut self.tokens, &self.options),
+            Err(e) => Err(e),
+        };
+        self.done = result.is_err();
+        Some(result.map_err(|e| self.tokens.with_excerpt(e)))
+    }
+
+    fn next_record(&mut self) -> Option<Result<JsonValue>> {
+        loop {
+            if self.record_read {
+                match self.tokens.next_record() {
+                    Ok(true) => {}
+                    Ok(false) => return None,
+                    Err(e) => {
+                        self.done = true;
+                        return Some(Err(e));
+                    }
+                }
+            }
+            self.record_read = true;
+
+            let result = match self.tokens.peek_token() {
+                Ok(token) if matches!(token.token, Token::Eof) => continue,
+                Ok(_) => parse_tokens(&mut self.tokens, &self.options),
+                Err(e) => Err(e),
+            };
+            self.done = matches!(result, Err(ParseError::Io(_)));
+            return Some(result.map_err(|e| self.tokens.with_excerpt(e)));
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for DocumentReader<R> {
+    type Item = Result<JsonValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.framing {
+            Framing::Concatenated => self.next_concatenated(),
+            Framing::RecordSeparated => self.next_record(),
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Like [`ndjson_reader`], with these options.
+    pub fn ndjson_reader<R: Read>(&self, reader: R) -> NdjsonReader<BufReader<R>> {
+        NdjsonReader::new(BufReader::new(reader), *self)
+    }
+
+    /// Like [`document_reader`], with these options.
+    pub fn document_reader<R: Read>(
+        &self,
+        reader: R,
+        framing: Framing,
+    ) -> DocumentReader<BufReader<R>> {
+        DocumentReader::new(BufReader::new(reader), *self, framing)
+    }
+}
+
+/// Iterates over the documents in newline-delimited JSON, one per line.
+pub fn ndjson_reader<R: Read>(reader: R) -> NdjsonReader<BufReader<R>> {
+    ParserOptions::default().ndjson_reader(reader)
+}
+
+/// Iterates over a stream of JSON documents separated as `framing` says.
+pub fn document_reader<R: Read>(reader: R, framing: Framing) -> DocumentReader<BufReader<R>> {
+    ParserOptions::default().document_reader(reader, framing)
+}
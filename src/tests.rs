@@ -1,34 +1,41 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
-    use crate::parser::{parse_json, JsonValue};
+    use crate::array::Array;
+    use crate::error::{Container, ParseError};
+    use crate::map::{Map, MapEntry};
+    use crate::number::{Decimal, Number};
+    use crate::options::{Dialect, DuplicateKeys, LoneSurrogates, ParserOptions};
+    use crate::parser::{
+        parse_json, parse_reader, parse_slice, parse_str, validate_json, validate_slice,
+        validate_str, JsonValue,
+    };
+    use crate::serializer::{Indent, PrettyOptions, SerializeOptions};
+    use crate::stream::{document_reader, ndjson_reader, Framing};
+
+    fn number(n: f64) -> JsonValue {
+        JsonValue::Number(Number::from_f64(n).unwrap())
+    }
 
     #[test]
     fn test_invalid_path() {
         let path = String::from("invalid/path");
         let result = parse_json(path);
-        assert!(result.is_err());
-
-        if let Err(e) = result {
-            assert_eq!(e.to_string(), "Falied to read File");
-        }
+        assert!(matches!(result, Err(ParseError::Io(_))));
     }
 
     #[test]
     fn test_step1_valid() {
         let path = String::from("./tests/step1/valid.json");
         let result = parse_json(path).expect("Error parsing JSON");
-        assert_eq!(result, JsonValue::Object(vec![]));
+        assert_eq!(result, JsonValue::Object(Map::new()));
     }
 
     #[test]
     fn test_step1_invalid() {
         let path = String::from("./tests/step1/invalid.json");
         let result = parse_json(path);
-        assert!(result.is_err());
-
-        if let Err(e) = result {
-            assert_eq!(e.to_string(), "Empty file");
-        }
+        assert!(matches!(result, Err(ParseError::Empty)));
     }
 
     #[test]
@@ -37,10 +44,9 @@ mod tests {
         let result = parse_json(path).expect("Falied to parse JSON");
         assert_eq!(
             result,
-            JsonValue::Object(vec![(
-                "key".to_string(),
-                JsonValue::String("value".to_string())
-            )])
+            JsonValue::Object(
+                vec![("key".to_string(), JsonValue::String("value".to_string()))].into()
+            )
         );
     }
 
@@ -48,11 +54,13 @@ mod tests {
     fn test_step2_invalid() {
         let path = String::from("./tests/step2/invalid.json");
         let result = parse_json(path);
-        assert!(result.is_err());
-
-        if let Err(e) = result {
-            assert_eq!(e.to_string(), "Trailing comma in object");
-        }
+        assert!(matches!(
+            result,
+            Err(ParseError::TrailingComma {
+                container: Container::Object,
+                ..
+            })
+        ));
     }
 
     #[test]
@@ -61,10 +69,13 @@ mod tests {
         let result = parse_json(path).expect("Falied to parse JSON");
         assert_eq!(
             result,
-            JsonValue::Object(vec![
-                ("key".to_string(), JsonValue::String("value".to_string())),
-                ("key2".to_string(), JsonValue::String("value".to_string()))
-            ])
+            JsonValue::Object(
+                vec![
+                    ("key".to_string(), JsonValue::String("value".to_string())),
+                    ("key2".to_string(), JsonValue::String("value".to_string()))
+                ]
+                .into()
+            )
         );
     }
 
@@ -72,11 +83,10 @@ mod tests {
     fn test_step2_invalid2() {
         let path = String::from("./tests/step2/invalid2.json");
         let result = parse_json(path);
-        assert!(result.is_err());
-
-        if let Err(e) = result {
-            assert_eq!(e.to_string(), "Unexpected character: k");
-        }
+        assert!(matches!(
+            result,
+            Err(ParseError::UnexpectedChar { ch: 'k', .. })
+        ));
     }
 
     #[test]
@@ -85,13 +95,16 @@ mod tests {
         let result = parse_json(path).expect("Falied to parse JSON");
         assert_eq!(
             result,
-            JsonValue::Object(vec![
-                ("key1".to_string(), JsonValue::Bool(true)),
-                ("key2".to_string(), JsonValue::Bool(false)),
-                ("key3".to_string(), JsonValue::Null),
-                ("key4".to_string(), JsonValue::String("value".to_string())),
-                ("key5".to_string(), JsonValue::Number(101.0))
-            ])
+            JsonValue::Object(
+                vec![
+                    ("key1".to_string(), JsonValue::Bool(true)),
+                    ("key2".to_string(), JsonValue::Bool(false)),
+                    ("key3".to_string(), JsonValue::Null),
+                    ("key4".to_string(), JsonValue::String("value".to_string())),
+                    ("key5".to_string(), number(101.0))
+                ]
+                .into()
+            )
         );
     }
 
@@ -99,11 +112,10 @@ mod tests {
     fn test_step3_invalid() {
         let path = String::from("./tests/step3/invalid.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
-
-        if let Err(e) = result {
-            assert_eq!(e.to_string(), "Unexpected character: F");
-        }
+        assert!(matches!(
+            result,
+            Err(ParseError::UnexpectedChar { ch: 'F', .. })
+        ));
     }
 
     #[test]
@@ -112,12 +124,15 @@ mod tests {
         let result = parse_json(path).expect("Falied to parse JSON");
         assert_eq!(
             result,
-            JsonValue::Object(vec![
-                ("key".to_string(), JsonValue::String("value".to_string())),
-                ("key-n".to_string(), JsonValue::Number(101.0)),
-                ("key-o".to_string(), JsonValue::Object(vec![])),
-                ("key-l".to_string(), JsonValue::Array(vec![]))
-            ])
+            JsonValue::Object(
+                vec![
+                    ("key".to_string(), JsonValue::String("value".to_string())),
+                    ("key-n".to_string(), number(101.0)),
+                    ("key-o".to_string(), JsonValue::Object(Map::new())),
+                    ("key-l".to_string(), JsonValue::Array(Array::new()))
+                ]
+                .into()
+            )
         );
     }
 
@@ -125,11 +140,10 @@ mod tests {
     fn test_step4_invalid() {
         let path = String::from("./tests/step4/invalid.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
-
-        if let Err(e) = result {
-            assert_eq!(e.to_string(), "Unexpected character: '");
-        }
+        assert!(matches!(
+            result,
+            Err(ParseError::UnexpectedChar { ch: '\'', .. })
+        ));
     }
 
     #[test]
@@ -138,346 +152,1541 @@ mod tests {
         let result = parse_json(path).expect("Falied to parse JSON");
         assert_eq!(
             result,
-            JsonValue::Object(vec![
-                ("key".to_string(), JsonValue::String("value".to_string())),
-                ("key-n".to_string(), JsonValue::Number(101.0)),
-                (
-                    "key-o".to_string(),
-                    JsonValue::Object(vec![(
-                        "inner key".to_string(),
-                        JsonValue::String("inner value".to_string())
-                    )])
-                ),
-                (
-                    "key-l".to_string(),
-                    JsonValue::Array(vec![JsonValue::String("list value".to_string())])
-                )
-            ])
+            JsonValue::Object(
+                vec![
+                    ("key".to_string(), JsonValue::String("value".to_string())),
+                    ("key-n".to_string(), number(101.0)),
+                    (
+                        "key-o".to_string(),
+                        JsonValue::Object(
+                            vec![(
+                                "inner key".to_string(),
+                                JsonValue::String("inner value".to_string())
+                            )]
+                            .into()
+                        )
+                    ),
+                    (
+                        "key-l".to_string(),
+                        JsonValue::Array(vec![JsonValue::String("list value".to_string())].into())
+                    )
+                ]
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_str() {
+        let result = parse_str(r#"{"key": "value"}"#).expect("Falied to parse JSON");
+        assert_eq!(
+            result,
+            JsonValue::Object(
+                vec![("key".to_string(), JsonValue::String("value".to_string()))].into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_slice_invalid_utf8() {
+        let result = parse_slice(b"{\n  \"key\": \"va\xFFlue\"\n}");
+        let Err(ParseError::InvalidUtf8 { at }) = result else {
+            panic!("Expected an invalid UTF-8 error, got {:?}", result);
+        };
+        assert_eq!(at.span.start.line, 2);
+        assert_eq!(at.span.start.column, 13);
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let file = std::fs::File::open("./tests/step2/valid2.json").expect("Falied to open file");
+        let from_reader = parse_reader(file).expect("Falied to parse JSON");
+        let from_path = parse_json(String::from("./tests/step2/valid2.json")).unwrap();
+        assert_eq!(from_reader, from_path);
+    }
+
+    // Hands out one byte per read, so every token straddles a buffer boundary
+    struct ByteByByte<'a>(&'a [u8]);
+
+    impl std::io::Read for ByteByByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.split_first() {
+                Some((&byte, rest)) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_small_reads() {
+        let input = r#"{"key": ["välue", -12.5e1, true, null]}"#;
+        let result = parse_reader(ByteByByte(input.as_bytes())).expect("Falied to parse JSON");
+        assert_eq!(result, parse_str(input).unwrap());
+    }
+
+    #[test]
+    fn test_error_excerpt_previous_line() {
+        let error = parse_str("{\n  \"key\": \"value\",\n\n}").expect_err("Expected an error");
+        let at = error.location().expect("Expected a location");
+
+        assert_eq!(at.span.start.line, 2);
+        assert_eq!(at.excerpt, "\"key\": \"value\",");
+    }
+
+    #[test]
+    fn test_error_position() {
+        let path = String::from("./tests/step2/invalid2.json");
+        let error = parse_json(path).expect_err("Expected an error");
+        let at = error.location().expect("Expected a location");
+
+        assert_eq!(at.span.start.line, 3);
+        assert_eq!(at.span.start.column, 3);
+        assert_eq!(at.span.start.offset, 22);
+        assert_eq!(at.excerpt, "key2: \"value\"");
+    }
+
+    #[test]
+    fn test_error_position_trailing_comma() {
+        let path = String::from("./tests/step2/invalid.json");
+        let error = parse_json(path).expect_err("Expected an error");
+        let span = error.location().expect("Expected a location").span;
+
+        // Points at the comma, not at the closing bracket
+        assert_eq!(span.start.line, 1);
+        assert_eq!(span.start.column, 16);
+        assert_eq!(span.end.column, 17);
+    }
+
+    // The validator has its own walk over the grammar, so make sure it agrees
+    // with the parser on every file we have, errors included
+    #[test]
+    fn test_validate_matches_parse() {
+        for dir in [
+            "step1",
+            "step2",
+            "step3",
+            "step4",
+            "custom",
+            "json_org_tests",
+            "literals",
+        ] {
+            for entry in std::fs::read_dir(format!("./tests/{}", dir)).unwrap() {
+                let path = entry.unwrap().path().display().to_string();
+                let parsed = parse_json(path.clone());
+                let validated = validate_json(path.clone());
+                match (parsed, validated) {
+                    (Ok(_), Ok(())) => {}
+                    (Err(p), Err(v)) => {
+                        assert_eq!(p.to_string(), v.to_string(), "{}", path);
+                        assert_eq!(p.location(), v.location(), "{}", path);
+                    }
+                    (p, v) => panic!("{}: parse gave {:?}, validate gave {:?}", path, p, v),
+                }
+            }
+        }
+    }
+
+    // Cases from JSONTestSuite the parser still gets wrong, remove them as they get fixed
+    const JSON_TEST_SUITE_KNOWN_FAILURES: &[&str] = &[];
+
+    // Runs every case in tests/json_test_suite, named like upstream JSONTestSuite: `y_` files
+    // must parse, `n_` files must fail and `i_` files may go either way. Run with
+    // `--nocapture` to see what the parser does with the `i_` ones.
+    #[test]
+    fn test_json_test_suite() {
+        let mut paths: Vec<_> = std::fs::read_dir("./tests/json_test_suite")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            // Skips upstream's LICENSE and the UPSTREAM commit file
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut failures = Vec::new();
+        let mut implementation_defined = Vec::new();
+        for path in paths {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            let input = std::fs::read(&path).unwrap();
+            let accepted = parse_slice(&input).is_ok();
+            assert_eq!(accepted, validate_slice(&input).is_ok(), "{}", name);
+
+            match name.split('_').next() {
+                Some("y") if !accepted => failures.push(name),
+                Some("n") if accepted => failures.push(name),
+                Some("i") => implementation_defined.push((name, accepted)),
+                _ => {}
+            }
+        }
+
+        println!("JSONTestSuite implementation defined cases:");
+        for (name, accepted) in &implementation_defined {
+            let outcome = if *accepted { "accepted" } else { "rejected" };
+            println!("  {} {}", outcome, name);
+        }
+        let mut known_failures = JSON_TEST_SUITE_KNOWN_FAILURES.to_vec();
+        known_failures.sort();
+        assert_eq!(
+            failures, known_failures,
+            "JSONTestSuite results changed, update JSON_TEST_SUITE_KNOWN_FAILURES"
+        );
+    }
+
+    #[test]
+    fn test_string_control_characters() {
+        for ch in ('\0'..='\x1F').chain(['\x7F']) {
+            let input = format!("[\"a{}b\"]", ch);
+            let result = parse_str(&input);
+            if ch == '\x7F' {
+                assert!(result.is_ok());
+                continue;
+            }
+            match result {
+                Err(ParseError::ControlCharacter { ch: found, at }) => {
+                    assert_eq!(found, ch);
+                    assert_eq!(at.span.start.column, 4);
+                }
+                other => panic!("{:?}: unexpected result {:?}", ch, other),
+            }
+            assert!(validate_str(&input).is_err());
+        }
+        assert_eq!(
+            parse_str("\"\u{2028}\"").unwrap(),
+            JsonValue::String("\u{2028}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        for input in [
+            r#"{"a": "bc"#,
+            r#"{"a": "b\"#,
+            r#"{"a": "\u00"#,
+            r#"{"a": "\"}"#,
+        ] {
+            match parse_str(input) {
+                Err(ParseError::UnterminatedString { at }) => {
+                    assert_eq!(at.span.start.column, 7, "{}", input);
+                    assert_eq!(at.span.end.column, 8, "{}", input);
+                }
+                other => panic!("{}: unexpected result {:?}", input, other),
+            }
+            assert!(matches!(
+                validate_str(input),
+                Err(ParseError::UnterminatedString { .. })
+            ));
+        }
+        assert_eq!(
+            parse_str("\"abc").unwrap_err().to_string(),
+            "Unterminated string"
+        );
+    }
+
+    #[test]
+    fn test_number_errors() {
+        for (input, reason, column) in [
+            ("[+1]", "Numbers can't start with a plus sign", 2),
+            ("[-]", "Expected digits after minus sign", 3),
+            ("[-.5]", "Expected digits after minus sign", 3),
+            ("[-01]", "Invalid number with leading zero", 4),
+            ("[1.]", "Expected digits after decimal point", 4),
+            ("[2.e3]", "Expected digits after decimal point", 4),
+            ("[1e]", "Expected digits in exponent", 4),
+            ("[1.5E+]", "Expected digits in exponent", 7),
+            ("[0e-x]", "Expected digits in exponent", 5),
+        ] {
+            match parse_str(input) {
+                Err(ParseError::InvalidNumber { reason: r, at }) => {
+                    assert_eq!(r, reason, "{}", input);
+                    assert_eq!(at.span.start.column, column, "{}", input);
+                }
+                other => panic!("{}: unexpected result {:?}", input, other),
+            }
+            assert!(validate_str(input).is_err(), "{}", input);
+        }
+
+        for input in ["0", "-0", "0e1", "0E+1", "-0.0e-0", "10.25", "1e400"] {
+            assert!(parse_str(input).is_ok(), "{}", input);
+        }
+        assert!(matches!(
+            parse_str("[1.2.3]"),
+            Err(ParseError::UnexpectedChar { ch: '.', .. })
+        ));
+    }
+
+    #[test]
+    fn test_json5() {
+        let path = String::from("./tests/json5/example.json5");
+        let options = ParserOptions {
+            dialect: Dialect::Json5,
+            ..Default::default()
+        };
+        let result = options
+            .parse_json(path.clone())
+            .expect("Failed to parse JSON5");
+        let string = |s: &str| JsonValue::String(s.to_string());
+        let lexeme = |s: &str| JsonValue::Number(Number::from_lexeme(s.to_string()));
+        assert_eq!(
+            result,
+            JsonValue::Object(
+                vec![
+                    (
+                        "unquoted".to_string(),
+                        string("and you can quote me on that")
+                    ),
+                    (
+                        "singleQuotes".to_string(),
+                        string("I can use \"double quotes\" here")
+                    ),
+                    ("lineBreaks".to_string(), string("Look, Mom! No \\n's!")),
+                    ("hexadecimal".to_string(), lexeme("912559")),
+                    ("leadingDecimalPoint".to_string(), lexeme("0.8675309")),
+                    ("andTrailing".to_string(), lexeme("8675309")),
+                    ("positiveSign".to_string(), lexeme("1")),
+                    ("trailingComma".to_string(), string("in objects")),
+                    (
+                        "andIn".to_string(),
+                        JsonValue::Array(vec![string("arrays")].into())
+                    ),
+                    ("backwardsCompatible".to_string(), string("with JSON")),
+                ]
+                .into()
+            )
+        );
+        assert!(options.validate_json(path.clone()).is_ok());
+        assert!(parse_json(path.clone()).is_err());
+        assert!(validate_json(path).is_err());
+
+        let result = options
+            .parse_str(
+                "[Infinity, -Infinity, +NaN, -0x10, 0XFFFFFFFFFFFFFFFFFFFF, 1.e2, '\\x41\\v\\0']",
+            )
+            .unwrap();
+        assert_eq!(
+            result.to_string(),
+            r#"[null,null,null,-16,1208925819614629174706175,1e2,"A\u000b\u0000"]"#
+        );
+        let JsonValue::Array(values) = &result else {
+            panic!("Expected an array");
+        };
+        let JsonValue::Number(n) = &values[1] else {
+            panic!("Expected a number");
+        };
+        assert_eq!(n.as_f64(), f64::NEG_INFINITY);
+        assert!(!n.is_finite() && n.as_decimal().is_none() && n.as_i64().is_none());
+        assert_ne!(values[2], values[2]);
+
+        let result = options
+            .parse_str("{null: 1, true: 2, NaN: 3, $a_1: 4, café: 5}")
+            .unwrap();
+        let JsonValue::Object(members) = &result else {
+            panic!("Expected an object");
+        };
+        let keys: Vec<_> = members.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["null", "true", "NaN", "$a_1", "café"]);
+        assert!(options.validate_str("{null: 1, NaN: 2, a: [1,],}").is_ok());
+        assert!(options.parse_str("[1,\u{C}\u{A0}\u{2028}2]").is_ok());
+        assert!(options.parse_str("{1: 1}").is_err());
+        assert!(options.validate_str("{1: 1}").is_err());
+        assert!(options.parse_str("[1,,]").is_err());
+
+        for (input, message) in [
+            ("[1] /* open", "Unterminated comment"),
+            ("[0x]", "Expected hex digits after 0x"),
+            ("[+x]", "Expected digits after plus sign"),
+            ("[-x]", "Expected digits after minus sign"),
+            ("['\\1']", "Invalid escape sequence: \\1"),
+            (
+                "['a\nb']",
+                "Invalid unescaped control character in string: '\\n'",
+            ),
+            ("[-Infinit]", "Invalid literal Infinit, expected Infinity"),
+            ("[abc]", "Expected a value"),
+        ] {
+            let error = options.parse_str(input).unwrap_err();
+            assert_eq!(error.to_string(), message, "{}", input);
+        }
+        let error = options.parse_str("[1] /* open").unwrap_err();
+        let at = error.location().unwrap();
+        assert_eq!((at.span.start.column, at.span.end.column), (5, 7));
+
+        // None of it is allowed in plain JSON
+        for input in [
+            "// c\n[]",
+            "{a: 1}",
+            "['a']",
+            "[.5]",
+            "[0x1]",
+            "[Infinity]",
+            "[1,]",
+            "[1,\u{C}2]",
+        ] {
+            assert!(parse_str(input).is_err(), "{}", input);
+            assert!(validate_str(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_jsonc() {
+        let path = String::from("./tests/jsonc/tsconfig.jsonc");
+        let options = ParserOptions {
+            dialect: Dialect::Jsonc,
+            ..Default::default()
+        };
+        let result = options
+            .parse_json(path.clone())
+            .expect("Failed to parse JSONC");
+        assert_eq!(
+            result.to_string(),
+            r#"{"compilerOptions":{"target":"es2016","module":"commonjs","strict":true,"lib":["dom","es2017"]}}"#
+        );
+        assert!(options.validate_json(path.clone()).is_ok());
+        assert!(parse_json(path).is_err());
+
+        // Only comments and trailing commas, the rest of JSON5 stays out
+        for input in [
+            "{a: 1}",
+            "['a']",
+            "[0x1]",
+            "[.5]",
+            "[+1]",
+            "[Infinity]",
+            r#"["\v"]"#,
+            "[1,,]",
+            "[1,\u{A0}2]",
+            "[/* open",
+        ] {
+            assert!(options.parse_str(input).is_err(), "{}", input);
+            assert!(options.validate_str(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let file = std::fs::File::open("./tests/ndjson/valid.ndjson").unwrap();
+        let values: Vec<_> = ndjson_reader(file)
+            .map(|value| value.expect("Failed to parse line").to_string())
+            .collect();
+        assert_eq!(
+            values,
+            [
+                r#"{"level":"info","msg":"started"}"#,
+                r#"{"level":"warn","msg":"slow","ms":1200}"#,
+                r#"["a",1]"#,
+                r#""just a string""#,
+            ]
+        );
+
+        // Stops at the first bad line unless asked to keep going
+        let file = std::fs::File::open("./tests/ndjson/invalid.jsonl").unwrap();
+        let results: Vec<_> = ndjson_reader(file).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        match &results[1] {
+            Err(ParseError::TrailingComma { at, .. }) => {
+                assert_eq!((at.span.start.line, at.span.start.column), (2, 9));
+                assert_eq!(at.span.start.offset, 19);
+                assert_eq!(at.excerpt, r#"{"id": 2,}"#);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let file = std::fs::File::open("./tests/ndjson/invalid.jsonl").unwrap();
+        let lines: Vec<_> = ndjson_reader(file)
+            .continue_on_error(true)
+            .map(|result| match result {
+                Ok(_) => None,
+                Err(e) => Some(e.location().unwrap().span.start.line),
+            })
+            .collect();
+        assert_eq!(lines, [None, Some(2), None, Some(4), None]);
+
+        // A truncated line is reported on that line, not the next one
+        for input in [
+            "{\"a\": 1}\n{\"b\":\n{\"c\": 3}\n",
+            "{\"a\": 1}\r\n{\"b\":\r\n{\"c\": 3}\r\n",
+        ] {
+            let results: Vec<_> = ndjson_reader(input.as_bytes())
+                .continue_on_error(true)
+                .collect();
+            assert_eq!(results.len(), 3);
+            assert!(results[0].is_ok() && results[2].is_ok());
+            match &results[1] {
+                Err(ParseError::UnexpectedEof { at, .. }) => {
+                    assert_eq!((at.span.start.line, at.span.start.column), (2, 6));
+                    assert_eq!(at.excerpt, "{\"b\":");
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+
+        assert_eq!(ndjson_reader("\n \n".as_bytes()).count(), 0);
+        let options = ParserOptions {
+            dialect: Dialect::Json5,
+            ..Default::default()
+        };
+        assert!(options
+            .ndjson_reader("{a: 1,}\n[2,]\n".as_bytes())
+            .all(|result| result.is_ok()));
+    }
+
+    #[test]
+    fn test_document_reader() {
+        let file = std::fs::File::open("./tests/sequences/concatenated.json").unwrap();
+        let values: Vec<_> = document_reader(file, Framing::Concatenated)
+            .map(|value| value.expect("Failed to parse document").to_string())
+            .collect();
+        assert_eq!(
+            values,
+            [
+                r#"{"id":1}"#,
+                r#"{"id":2}"#,
+                "[3,4]",
+                r#""five""#,
+                r#"{"id":6}"#
+            ]
+        );
+        let values: Vec<_> = document_reader("{}{}[]".as_bytes(), Framing::Concatenated)
+            .map(|value| value.unwrap().to_string())
+            .collect();
+        assert_eq!(values, ["{}", "{}", "[]"]);
+        assert_eq!(
+            document_reader(" \n".as_bytes(), Framing::Concatenated).count(),
+            0
+        );
+
+        // Concatenated values have nothing to recover on, so the first error is the last item
+        let results: Vec<_> =
+            document_reader("[1] [2,] [3]".as_bytes(), Framing::Concatenated).collect();
+        assert_eq!(results.len(), 2);
+        match &results[1] {
+            Err(ParseError::TrailingComma { at, .. }) => assert_eq!(at.span.start.offset, 6),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Bad records are reported and skipped, empty ones are ignored
+        let file = std::fs::File::open("./tests/sequences/records.json-seq").unwrap();
+        let results: Vec<_> = document_reader(file, Framing::RecordSeparated).collect();
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_ref().unwrap().to_string(), r#"{"id":1}"#);
+        match &results[1] {
+            // The record ends where the next one starts
+            Err(ParseError::UnexpectedEof { at, .. }) => assert_eq!(at.span.start.line, 3),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(results[2].as_ref().unwrap().to_string(), "[3]");
+        match &results[3] {
+            Err(ParseError::UnexpectedChar { ch: 'x', at }) => {
+                assert_eq!((at.span.start.line, at.span.start.column), (5, 12));
+                assert_eq!(at.excerpt, "\u{1e}{\"id\": 4} x");
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(results[4].as_ref().unwrap().to_string(), r#""five""#);
+
+        // A truncated string ends at the next separator instead of swallowing the record after it
+        let results: Vec<_> =
+            document_reader("\x1e\"abc\x1e\"def\"".as_bytes(), Framing::RecordSeparated).collect();
+        assert!(matches!(
+            results[0],
+            Err(ParseError::UnterminatedString { .. })
+        ));
+        assert_eq!(results[1].as_ref().unwrap().to_string(), r#""def""#);
+
+        // A separator is not whitespace anywhere else
+        assert!(document_reader("\x1e{}".as_bytes(), Framing::Concatenated)
+            .next()
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let input = r#"{"a": 1, "b": {"a": 2}, "a": 3, "c": 4, "a": 5}"#;
+        let parse = |duplicate_keys| {
+            let options = ParserOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            assert_eq!(
+                options.validate_str(input).is_ok(),
+                options.parse_str(input).is_ok()
+            );
+            options.parse_str(input).map(|value| value.to_string())
+        };
+
+        assert_eq!(
+            parse(DuplicateKeys::Allow).unwrap(),
+            r#"{"a":1,"b":{"a":2},"a":3,"c":4,"a":5}"#
+        );
+        assert_eq!(
+            parse(DuplicateKeys::KeepFirst).unwrap(),
+            r#"{"a":1,"b":{"a":2},"c":4}"#
+        );
+        assert_eq!(
+            parse(DuplicateKeys::KeepLast).unwrap(),
+            r#"{"a":5,"b":{"a":2},"c":4}"#
+        );
+        match parse(DuplicateKeys::Error) {
+            Err(ParseError::DuplicateKey { key, first, at }) => {
+                assert_eq!(key, "a");
+                assert_eq!((first.column, at.span.start.column), (2, 25));
+                assert_eq!(at.excerpt, input);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            ..Default::default()
+        };
+        // Keys only clash within the same object
+        assert!(options.parse_str(r#"[{"a": 1}, {"a": {"a": 2}}]"#).is_ok());
+        assert!(options
+            .validate_str(r#"[{"a": 1}, {"a": {"a": 2}}]"#)
+            .is_ok());
+        // Escapes are compared by what they decode to
+        assert!(options.validate_str(r#"{"a": 1, "\u0061": 2}"#).is_err());
+        let error = options
+            .parse_str("{\n  \"id\": 1,\n  \"id\": 2\n}")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"Duplicate key "id", first seen at line 2 column 3"#
+        );
+        assert_eq!(error.location().unwrap().span.start.line, 3);
+    }
+
+    #[test]
+    fn test_map() {
+        let mut map = Map::new();
+        assert_eq!(map.insert("b".to_string(), number(1.0)), None);
+        assert_eq!(map.insert("a".to_string(), number(2.0)), None);
+        assert_eq!(map.insert("b".to_string(), number(3.0)), Some(number(1.0)));
+        assert_eq!(map.len(), 2);
+        assert!(map.contains_key("a") && !map.contains_key("c"));
+        assert_eq!(map.get("b"), Some(&number(3.0)));
+        *map.get_mut("a").unwrap() = JsonValue::Null;
+        // Replacing a value keeps its place
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "a"]);
+
+        match map.entry("c".to_string()) {
+            MapEntry::Vacant(entry) => {
+                entry.insert(JsonValue::Bool(true));
+            }
+            MapEntry::Occupied(_) => panic!("c shouldn't be there yet"),
+        }
+        map.entry("c".to_string())
+            .and_modify(|value| *value = JsonValue::Bool(false))
+            .or_insert(JsonValue::Null);
+        map.entry("d".to_string())
+            .or_insert_with(|| JsonValue::Array(Array::new()));
+        assert_eq!(map.get("c"), Some(&JsonValue::Bool(false)));
+        assert_eq!(
+            JsonValue::Object(map).to_string(),
+            r#"{"b":3,"a":null,"c":false,"d":[]}"#
+        );
+
+        let mut result = parse_str(r#"{"x": 1, "y": 2, "x": 3, "z": 4}"#).unwrap();
+        let JsonValue::Object(map) = &mut result else {
+            panic!("Expected an object");
+        };
+        // Every copy of a duplicate key is kept, lookups see the last one
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get("x"), Some(&number(3.0)));
+        for (_, value) in map.iter_mut() {
+            *value = JsonValue::Null;
+        }
+        assert_eq!(map.remove("x"), Some(JsonValue::Null));
+        assert_eq!(map.remove("x"), None);
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["y", "z"]);
+        assert_eq!(map.get("z"), Some(&JsonValue::Null));
+        if let MapEntry::Occupied(entry) = map.entry("y".to_string()) {
+            entry.remove();
+        }
+        assert_eq!(result.to_string(), r#"{"z":null}"#);
+
+        // Lookups stay fast on big objects
+        let input = format!(
+            "{{{}}}",
+            (0..20_000)
+                .map(|i| format!("\"flag{}\": {}", i, i))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let result = parse_str(&input).unwrap();
+        let JsonValue::Object(map) = &result else {
+            panic!("Expected an object");
+        };
+        for i in (0..20_000).step_by(7) {
+            assert_eq!(map.get(&format!("flag{}", i)), Some(&number(i as f64)));
+        }
+        let collected: Map = map
+            .iter()
+            .filter(|(key, _)| key.ends_with('7'))
+            .map(|(key, _)| (key.clone(), JsonValue::Null))
+            .collect();
+        assert_eq!(collected.len(), 2_000);
+    }
+
+    #[test]
+    fn test_accessors() {
+        let value = parse_str(
+            r#"{"users": [{"name": "Ada", "age": 36, "admin": true, "team": null}], "count": 1.5}"#,
+        )
+        .unwrap();
+
+        assert_eq!(value["users"][0]["name"].as_str(), Some("Ada"));
+        assert_eq!(value["users"][0]["age"].as_i64(), Some(36));
+        assert_eq!(value["users"][0]["age"].as_u64(), Some(36));
+        assert_eq!(value["users"][0]["admin"].as_bool(), Some(true));
+        assert!(value["users"][0]["team"].is_null());
+        assert_eq!(value["count"].as_f64(), Some(1.5));
+        assert_eq!(value["count"].as_i64(), None);
+        assert_eq!(value["users"].as_array().map(|users| users.len()), Some(1));
+        assert!(value.as_object().unwrap().contains_key("count"));
+        assert!(value.is_object() && value["users"].is_array() && value["count"].is_number());
+        assert!(value["users"][0]["name"].is_string() && value["users"][0]["admin"].is_bool());
+
+        // Missing keys, elements and wrong types all give Null
+        assert!(value["missing"].is_null());
+        assert!(value["users"][5]["name"].is_null());
+        assert!(value["count"]["name"][0].is_null());
+        assert_eq!(value["users"]["name"].as_str(), None);
+        assert_eq!(value.get("missing"), None);
+        assert_eq!(value["users"].get_index(0), Some(&value["users"][0]));
+        assert_eq!(value["users"][0]["name"].as_f64(), None);
+        assert_eq!(value.as_str(), None);
+
+        let mut value = value;
+        assert!(value.as_array_mut().is_none());
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("count".to_string(), JsonValue::Bool(false));
+        assert_eq!(value["count"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn test_literals() {
+        for entry in std::fs::read_dir("./tests/literals").unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let result = parse_json(path.display().to_string());
+            if name.starts_with("invalid") {
+                assert!(
+                    matches!(result, Err(ParseError::InvalidLiteral { .. })),
+                    "{}: {:?}",
+                    name,
+                    result
+                );
+            } else {
+                assert!(result.is_ok(), "{}: {:?}", name, result);
+            }
+        }
+
+        let error = parse_str("[true,\n  fasle]").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid literal fasle, expected false");
+        match error {
+            ParseError::InvalidLiteral { at, .. } => {
+                assert_eq!((at.span.start.line, at.span.start.column), (2, 3));
+                assert_eq!(at.span.end.column, 8);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(matches!(
+            validate_str("nul"),
+            Err(ParseError::InvalidLiteral {
+                expected: "null",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_nested() {
+        assert!(validate_str(r#"{"a": [1, {"b": []}, {}], "c": {"d": null}}"#).is_ok());
+        assert!(matches!(
+            validate_str(r#"{"a": [1, 2,]}"#),
+            Err(ParseError::TrailingComma {
+                container: Container::Array,
+                ..
+            })
+        ));
+        assert!(matches!(
+            validate_str(r#"{"a": [1 2]}"#),
+            Err(ParseError::UnexpectedToken { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unicode_escape() {
+        let result = parse_str(r#"["\u0041", "\u00e9t\u00E9"]"#).expect("Falied to parse JSON");
+        assert_eq!(
+            result,
+            JsonValue::Array(
+                vec![
+                    JsonValue::String("A".to_string()),
+                    JsonValue::String("été".to_string())
+                ]
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_surrogate_pair() {
+        let result =
+            parse_str(r#"["\ud83d\ude00 and \uD834\uDD1E"]"#).expect("Falied to parse JSON");
+        assert_eq!(
+            result,
+            JsonValue::Array(vec![JsonValue::String("😀 and 𝄞".to_string())].into())
+        );
+    }
+
+    #[test]
+    fn test_lone_surrogate_error() {
+        for input in [
+            r#"["\ud83d"]"#,
+            r#"["\ude00"]"#,
+            r#"["\ud83d\u0041"]"#,
+            r#"["\ud83d\ud83d\ude00"]"#,
+        ] {
+            let result = parse_str(input);
+            let Err(ParseError::LoneSurrogate { at, .. }) = result else {
+                panic!(
+                    "{}: expected a lone surrogate error, got {:?}",
+                    input, result
+                );
+            };
+            // Points at the lone escape itself
+            assert_eq!(at.span.start.column, 3, "{}", input);
+            assert_eq!(at.span.end.column, 9, "{}", input);
+            assert!(validate_str(input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_scalar_root() {
+        assert_eq!(parse_str("42").unwrap(), number(42.0));
+        assert_eq!(
+            parse_str(r#" "hello" "#).unwrap(),
+            JsonValue::String("hello".to_string())
+        );
+        assert_eq!(parse_str("null").unwrap(), JsonValue::Null);
+        assert!(validate_str("true").is_ok());
+        assert!(matches!(
+            parse_str("1 2"),
+            Err(ParseError::TrailingCharacters { .. })
+        ));
+
+        let options = ParserOptions {
+            require_container_root: true,
+            ..Default::default()
+        };
+        let error = options.parse_str("\n  42").unwrap_err();
+        match error {
+            ParseError::NonContainerRoot { at } => {
+                assert_eq!((at.span.start.line, at.span.start.column), (2, 3))
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(matches!(
+            options.validate_str("42"),
+            Err(ParseError::NonContainerRoot { .. })
+        ));
+        assert!(options.parse_str("[42]").is_ok());
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let limit = ParserOptions::DEFAULT_MAX_DEPTH;
+
+        assert!(parse_str(&nested(limit)).is_ok());
+        assert!(validate_str(&nested(limit + 1)).is_ok());
+        let error = parse_str(&nested(limit + 2)).unwrap_err();
+        match error {
+            ParseError::DepthExceeded { limit: l, at } => {
+                assert_eq!(l, limit);
+                assert_eq!(at.span.start.column, limit + 2);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(validate_str(&nested(limit + 2)).is_err());
+
+        let options = ParserOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert!(options.parse_str(r#"{"a": [], "b": 1}"#).is_ok());
+        assert!(options.parse_str(r#"{"a": [1]}"#).is_err());
+        assert!(options.validate_str(r#"{"a": [1]}"#).is_err());
+        assert!(options.parse_str("1").is_ok());
+
+        let options = ParserOptions {
+            max_depth: None,
+            ..Default::default()
+        };
+        assert!(options.parse_str(&nested(100_000)).is_ok());
+        assert!(options.validate_str(&nested(100_000)).is_ok());
+        let objects = format!("{}null{}", r#"{"a":"#.repeat(100_000), "}".repeat(100_000));
+        assert!(options.parse_str(&objects).is_ok());
+    }
+
+    #[test]
+    fn test_deep_values_after_parsing() {
+        let options = ParserOptions {
+            max_depth: None,
+            ..Default::default()
+        };
+        // Deep enough that the default indent passes u16::MAX columns
+        let depth = 33_000;
+        let arrays = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let objects = format!("{}null{}", r#"{"a":"#.repeat(depth), "}".repeat(depth));
+
+        for input in [arrays, objects] {
+            let value = options.parse_str(&input).unwrap();
+            assert_eq!(value.to_string(), input);
+            assert_eq!(value, options.parse_str(&input).unwrap());
+
+            // The pretty output is gigabytes at this depth, so only check it gets written
+            let pretty = SerializeOptions {
+                pretty: Some(PrettyOptions::default()),
+                ..Default::default()
+            };
+            pretty.to_writer(&value, std::io::sink()).unwrap();
+            assert!(format!("{:?}", value).len() > input.len());
+        }
+    }
+
+    #[test]
+    fn test_lone_surrogate_replace() {
+        let options = ParserOptions {
+            lone_surrogates: LoneSurrogates::Replace,
+            ..Default::default()
+        };
+        let result = options
+            .parse_str(r#"["\ud83dx\ude00", "\ud83d\ud83d\ude00"]"#)
+            .expect("Falied to parse JSON");
+        assert_eq!(
+            result,
+            JsonValue::Array(
+                vec![
+                    JsonValue::String("\u{FFFD}x\u{FFFD}".to_string()),
+                    JsonValue::String("\u{FFFD}😀".to_string())
+                ]
+                .into()
+            )
         );
+        assert!(options.validate_str(r#"["\ud83d"]"#).is_ok());
+    }
+
+    #[test]
+    fn test_lone_surrogate_round_trip() {
+        let options = ParserOptions {
+            lone_surrogates: LoneSurrogates::Preserve,
+            ..Default::default()
+        };
+        let input = r#"["a\ud83d","\ude00b","\ud83d\ude00\ud800\n","\\ud800"]"#;
+        let result = options.parse_str(input).expect("Falied to parse JSON");
+        assert_eq!(
+            result,
+            JsonValue::Array(
+                vec![
+                    JsonValue::Utf16String(vec![0x61, 0xD83D]),
+                    JsonValue::Utf16String(vec![0xDE00, 0x62]),
+                    JsonValue::Utf16String(vec![0xD83D, 0xDE00, 0xD800, 0x0A]),
+                    // An escaped backslash is just text, it can't be mistaken for a surrogate
+                    JsonValue::String("\\ud800".to_string()),
+                ]
+                .into()
+            )
+        );
+        assert_eq!(
+            result.to_string(),
+            r#"["a\ud83d","\ude00b","😀\ud800\n","\\ud800"]"#
+        );
+        assert_eq!(options.parse_str(&result.to_string()).unwrap(), result);
+        assert_ne!(
+            options.parse_str(r#"["\ud800"]"#).unwrap(),
+            options.parse_str(r#"["\\ud800"]"#).unwrap()
+        );
+        assert!(result[0].is_string() && result[0].as_str().is_none());
+
+        // Keys have to be valid Unicode to go in a Map
+        for input in [r#"{"a\udc00": 1}"#, r#"[{"a": 1, "\ud800": 2}]"#] {
+            assert!(matches!(
+                options.parse_str(input),
+                Err(ParseError::LoneSurrogate {
+                    code: 0xD800 | 0xDC00,
+                    ..
+                })
+            ));
+            assert!(options.validate_str(input).is_err());
+        }
+        assert!(options.validate_str(input).is_ok());
+    }
+
+    #[test]
+    fn test_number_precision() {
+        let result = parse_str("[9007199254740993, 0.1, -170141183460469231731687303715884105728]")
+            .expect("Falied to parse JSON");
+        let JsonValue::Array(numbers) = result else {
+            panic!("Expected an array");
+        };
+        let numbers: Vec<&Number> = numbers
+            .iter()
+            .map(|n| match n {
+                JsonValue::Number(n) => n,
+                _ => panic!("Expected a number"),
+            })
+            .collect();
+
+        assert_eq!(numbers[0].as_i64(), Some(9007199254740993));
+        assert_eq!(numbers[0].as_u64(), Some(9007199254740993));
+        assert_eq!(numbers[0].as_str(), "9007199254740993");
+        assert_eq!(numbers[1].as_f64(), 0.1);
+        assert_eq!(numbers[1].as_i64(), None);
+        assert_eq!(numbers[1].as_str(), "0.1");
+        assert_eq!(numbers[2].as_i64(), None);
+        assert_eq!(numbers[2].as_u64(), None);
+        assert_eq!(numbers[2].as_i128(), Some(i128::MIN));
+    }
+
+    #[test]
+    fn test_number_integer_forms() {
+        let result = parse_str("[1.0, 12e2, 1500e-2, -0, 1.5]").expect("Falied to parse JSON");
+        let JsonValue::Array(numbers) = result else {
+            panic!("Expected an array");
+        };
+        let as_i64: Vec<Option<i64>> = numbers
+            .iter()
+            .map(|n| match n {
+                JsonValue::Number(n) => n.as_i64(),
+                _ => panic!("Expected a number"),
+            })
+            .collect();
+        assert_eq!(as_i64, vec![Some(1), Some(1200), Some(15), Some(0), None]);
+    }
+
+    #[test]
+    fn test_number_decimal() {
+        let result = parse_str("[-123.4500e-10]").expect("Falied to parse JSON");
+        let JsonValue::Array(numbers) = result else {
+            panic!("Expected an array");
+        };
+        let JsonValue::Number(n) = &numbers[0] else {
+            panic!("Expected a number");
+        };
+        let decimal = n.as_decimal().unwrap();
+        assert_eq!(
+            decimal,
+            Decimal {
+                negative: true,
+                digits: "12345".to_string(),
+                exponent: -12,
+            }
+        );
+        assert_eq!(decimal.to_string(), "-1.2345e-8");
+
+        let big = parse_str("[123456789012345678901234567890.5]").unwrap();
+        let JsonValue::Array(numbers) = big else {
+            panic!("Expected an array");
+        };
+        let JsonValue::Number(n) = &numbers[0] else {
+            panic!("Expected a number");
+        };
+        assert_eq!(
+            n.as_decimal().unwrap().to_string(),
+            "1.234567890123456789012345678905e29"
+        );
+        assert_eq!(Number::from(42).as_decimal().unwrap().to_string(), "42");
+        assert_eq!(
+            Number::from_f64(0.00125)
+                .unwrap()
+                .as_decimal()
+                .unwrap()
+                .to_string(),
+            "0.00125"
+        );
+    }
+
+    #[test]
+    fn test_number_equality() {
+        assert_eq!(
+            parse_str("[1.50, 0]").unwrap(),
+            parse_str("[15e-1, -0.0]").unwrap()
+        );
+        assert_ne!(
+            parse_str("[9007199254740993]").unwrap(),
+            parse_str("[9007199254740992]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_serialize() {
+        let value = parse_str(
+            r#" { "a" : [ 1 , 2.50 , -3e10 ] , "b" : { } , "c" : [ true , false , null ] } "#,
+        )
+        .expect("Falied to parse JSON");
+        assert_eq!(
+            value.to_string(),
+            r#"{"a":[1,2.50,-3e10],"b":{},"c":[true,false,null]}"#
+        );
+    }
+
+    #[test]
+    fn test_serialize_escapes() {
+        let value = JsonValue::Array(
+            vec![JsonValue::String(
+                "quote \" slash \\ tab \t nl \n bell \x07 é 😀".to_string(),
+            )]
+            .into(),
+        );
+        assert_eq!(
+            value.to_string(),
+            r#"["quote \" slash \\ tab \t nl \n bell \u0007 é 😀"]"#
+        );
+
+        let options = SerializeOptions {
+            escape_non_ascii: true,
+            ..SerializeOptions::default()
+        };
+        assert_eq!(
+            options.to_string(&value),
+            r#"["quote \" slash \\ tab \t nl \n bell \u0007 \u00e9 \ud83d\ude00"]"#
+        );
+    }
+
+    #[test]
+    fn test_pretty() {
+        let value =
+            parse_json(String::from("./tests/step4/valid2.json")).expect("Falied to parse JSON");
+        // The whole object is too wide for one line, but each member fits
+        assert_eq!(
+            value.to_pretty_string(),
+            r#"{
+  "key": "value",
+  "key-n": 101,
+  "key-o": {"inner key": "inner value"},
+  "key-l": ["list value"]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_pretty_options() {
+        let value =
+            parse_str(r#"{"a": [1, 2, {"b": null}], "c": {}}"#).expect("Falied to parse JSON");
+        let options = SerializeOptions {
+            pretty: Some(PrettyOptions {
+                indent: Indent::Tabs,
+                max_width: 0,
+                space_after_colon: false,
+                trailing_newline: false,
+            }),
+            ..SerializeOptions::default()
+        };
+        assert_eq!(
+            options.to_string(&value),
+            "{\n\t\"a\":[\n\t\t1,\n\t\t2,\n\t\t{\n\t\t\t\"b\":null\n\t\t}\n\t],\n\t\"c\":{}\n}"
+        );
+
+        // Exactly at the limit still fits, one column less doesn't
+        let narrow = |max_width| SerializeOptions {
+            pretty: Some(PrettyOptions {
+                max_width,
+                ..PrettyOptions::default()
+            }),
+            ..SerializeOptions::default()
+        };
+        let array = parse_str("[1, 2, 3]").unwrap();
+        assert_eq!(narrow(9).to_string(&array), "[1, 2, 3]\n");
+        assert_eq!(narrow(8).to_string(&array), "[\n  1,\n  2,\n  3\n]\n");
+        assert_eq!(format!("{:#}", array), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        for path in [
+            "./tests/json_org_tests/pass1.json",
+            "./tests/json_org_tests/pass2.json",
+            "./tests/json_org_tests/pass3.json",
+            "./tests/custom/valid.json",
+        ] {
+            let value = parse_json(String::from(path)).expect("Falied to parse JSON");
+            let mut written = Vec::new();
+            value.to_writer(&mut written).expect("Falied to write JSON");
+            assert_eq!(parse_slice(&written).unwrap(), value, "{}", path);
+            assert_eq!(String::from_utf8(written).unwrap(), value.to_string());
+        }
     }
 
     // Custom test that I made to guarantee basic checks like nulls, nesting
     // Negative and floating point numbers
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_custom_valid() {
         let path = String::from("./tests/custom/valid.json");
         let result = parse_json(path).expect("Falied to parse JSON");
         assert_eq!(
             result,
-            JsonValue::Object(vec![
-                ("string".to_string(), JsonValue::String("value".to_string())),
-                ("positive_number".to_string(), JsonValue::Number(123.456)),
-                ("negative_number".to_string(), JsonValue::Number(-789.123)),
-                ("boolean_true".to_string(), JsonValue::Bool(true)),
-                ("boolean_false".to_string(), JsonValue::Bool(false)),
-                ("null_value".to_string(), JsonValue::Null),
-                (
-                    "object".to_string(),
-                    JsonValue::Object(vec![
-                        (
-                            "nested_string".to_string(),
-                            JsonValue::String("nested_value".to_string())
-                        ),
-                        ("nested_number".to_string(), JsonValue::Number(789.0)),
-                        (
-                            "nested_negative_number".to_string(),
-                            JsonValue::Number(-456.0)
-                        ),
-                        ("nested_float".to_string(), JsonValue::Number(0.987)),
-                        (
-                            "nested_object".to_string(),
-                            JsonValue::Object(vec![(
-                                "inner_key".to_string(),
-                                JsonValue::String("inner_value".to_string())
-                            )])
-                        ),
-                        (
-                            "nested_array".to_string(),
-                            JsonValue::Array(vec![
-                                JsonValue::Number(1.0),
-                                JsonValue::Number(-2.0),
-                                JsonValue::Number(3.14),
-                                JsonValue::String("four".to_string()),
-                                JsonValue::Bool(true),
-                                JsonValue::Null
-                            ])
+            JsonValue::Object(
+                vec![
+                    ("string".to_string(), JsonValue::String("value".to_string())),
+                    ("positive_number".to_string(), number(123.456)),
+                    ("negative_number".to_string(), number(-789.123)),
+                    ("boolean_true".to_string(), JsonValue::Bool(true)),
+                    ("boolean_false".to_string(), JsonValue::Bool(false)),
+                    ("null_value".to_string(), JsonValue::Null),
+                    (
+                        "object".to_string(),
+                        JsonValue::Object(
+                            vec![
+                                (
+                                    "nested_string".to_string(),
+                                    JsonValue::String("nested_value".to_string())
+                                ),
+                                ("nested_number".to_string(), number(789.0)),
+                                ("nested_negative_number".to_string(), number(-456.0)),
+                                ("nested_float".to_string(), number(0.987)),
+                                (
+                                    "nested_object".to_string(),
+                                    JsonValue::Object(
+                                        vec![(
+                                            "inner_key".to_string(),
+                                            JsonValue::String("inner_value".to_string())
+                                        )]
+                                        .into()
+                                    )
+                                ),
+                                (
+                                    "nested_array".to_string(),
+                                    JsonValue::Array(
+                                        vec![
+                                            number(1.0),
+                                            number(-2.0),
+                                            number(3.14),
+                                            JsonValue::String("four".to_string()),
+                                            JsonValue::Bool(true),
+                                            JsonValue::Null
+                                        ]
+                                        .into()
+                                    )
+                                )
+                            ]
+                            .into()
+                        )
+                    ),
+                    (
+                        "array".to_string(),
+                        JsonValue::Array(
+                            vec![
+                                JsonValue::String("string_in_array".to_string()),
+                                number(42.0),
+                                number(-99.0),
+                                number(3.1415),
+                                JsonValue::Bool(false),
+                                JsonValue::Null,
+                                JsonValue::Object(
+                                    vec![(
+                                        "array_object_key".to_string(),
+                                        JsonValue::String("array_object_value".to_string())
+                                    )]
+                                    .into()
+                                ),
+                                JsonValue::Array(
+                                    vec![
+                                        JsonValue::String("nested_array_in_array".to_string()),
+                                        number(-45.67)
+                                    ]
+                                    .into()
+                                )
+                            ]
+                            .into()
                         )
-                    ])
-                ),
-                (
-                    "array".to_string(),
-                    JsonValue::Array(vec![
-                        JsonValue::String("string_in_array".to_string()),
-                        JsonValue::Number(42.0),
-                        JsonValue::Number(-99.0),
-                        JsonValue::Number(3.1415),
-                        JsonValue::Bool(false),
-                        JsonValue::Null,
-                        JsonValue::Object(vec![(
-                            "array_object_key".to_string(),
-                            JsonValue::String("array_object_value".to_string())
-                        )]),
-                        JsonValue::Array(vec![
-                            JsonValue::String("nested_array_in_array".to_string()),
-                            JsonValue::Number(-45.67)
-                        ])
-                    ])
-                ),
-                ("empty_object".to_string(), JsonValue::Object(vec![])),
-                ("empty_array".to_string(), JsonValue::Array(vec![]))
-            ])
+                    ),
+                    ("empty_object".to_string(), JsonValue::Object(Map::new())),
+                    ("empty_array".to_string(), JsonValue::Array(Array::new()))
+                ]
+                .into()
+            )
         );
     }
 
     #[test]
     fn test_json_org_fail_1() {
+        // Written against RFC 4627, RFC 8259 allows a string as the whole document
         let path = String::from("./tests/json_org_tests/fail1.json");
-        let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        let options = ParserOptions {
+            require_container_root: true,
+            ..Default::default()
+        };
+        let result = options.parse_json(path.clone());
+        assert!(matches!(result, Err(ParseError::NonContainerRoot { .. })));
+        assert!(parse_json(path).is_ok());
     }
 
     #[test]
     fn test_json_org_fail_10() {
         let path = String::from("./tests/json_org_tests/fail10.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_11() {
         let path = String::from("./tests/json_org_tests/fail11.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_12() {
         let path = String::from("./tests/json_org_tests/fail12.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_13() {
         let path = String::from("./tests/json_org_tests/fail13.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_14() {
         let path = String::from("./tests/json_org_tests/fail14.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_15() {
         let path = String::from("./tests/json_org_tests/fail15.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_16() {
         let path = String::from("./tests/json_org_tests/fail16.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_17() {
         let path = String::from("./tests/json_org_tests/fail17.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_18() {
+        // json.org caps nesting at 19 levels, which the default limit is well past
         let path = String::from("./tests/json_org_tests/fail18.json");
-        let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        let options = ParserOptions {
+            max_depth: Some(19),
+            ..Default::default()
+        };
+        let result = options.parse_json(path.clone());
+        assert!(matches!(
+            result,
+            Err(ParseError::DepthExceeded { limit: 19, .. })
+        ));
+        assert!(options.validate_json(path.clone()).is_err());
+        assert!(parse_json(path).is_ok());
+        assert!(options
+            .parse_json(String::from("./tests/json_org_tests/pass2.json"))
+            .is_ok());
     }
 
     #[test]
     fn test_json_org_fail_19() {
         let path = String::from("./tests/json_org_tests/fail19.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_2() {
         let path = String::from("./tests/json_org_tests/fail2.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_20() {
         let path = String::from("./tests/json_org_tests/fail20.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_21() {
         let path = String::from("./tests/json_org_tests/fail21.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_22() {
         let path = String::from("./tests/json_org_tests/fail22.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_23() {
         let path = String::from("./tests/json_org_tests/fail23.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_24() {
         let path = String::from("./tests/json_org_tests/fail24.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_25() {
         let path = String::from("./tests/json_org_tests/fail25.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_26() {
         let path = String::from("./tests/json_org_tests/fail26.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_27() {
         let path = String::from("./tests/json_org_tests/fail27.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_28() {
         let path = String::from("./tests/json_org_tests/fail28.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_29() {
         let path = String::from("./tests/json_org_tests/fail29.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_3() {
         let path = String::from("./tests/json_org_tests/fail3.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_30() {
         let path = String::from("./tests/json_org_tests/fail30.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_31() {
         let path = String::from("./tests/json_org_tests/fail31.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_32() {
         let path = String::from("./tests/json_org_tests/fail32.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_33() {
         let path = String::from("./tests/json_org_tests/fail33.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_4() {
         let path = String::from("./tests/json_org_tests/fail4.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_5() {
         let path = String::from("./tests/json_org_tests/fail5.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_6() {
         let path = String::from("./tests/json_org_tests/fail6.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_7() {
         let path = String::from("./tests/json_org_tests/fail7.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_8() {
         let path = String::from("./tests/json_org_tests/fail8.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_fail_9() {
         let path = String::from("./tests/json_org_tests/fail9.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_json_org_pass_1() {
         let path = String::from("./tests/json_org_tests/pass1.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), false);
+        assert!(result.is_ok());
     }
 
     #[test]
     fn test_json_org_pass_2() {
         let path = String::from("./tests/json_org_tests/pass2.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), false);
+        assert!(result.is_ok());
     }
 
     #[test]
     fn test_json_org_pass_3() {
         let path = String::from("./tests/json_org_tests/pass3.json");
         let result = parse_json(path);
-        assert_eq!(result.is_err(), false);
+        assert!(result.is_ok());
     }
 }
@@ -0,0 +1,60 @@
+use cc_json_parser::{parse_json, parse_str};
+use std::{env, fs, path::PathBuf, process::Command};
+
+fn run(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_cc-json-parser"))
+        .args(args)
+        .output()
+        .expect("Failed to run cc-json-parser")
+}
+
+// A path in the temp directory that no other test uses
+fn output_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("cc-json-parser-{}-{}", std::process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_minify_to_file() {
+    let input = "./tests/json_org_tests/pass1.json";
+    let output = output_path("pass1.min.json");
+    let result = run(&["minify", input, output.to_str().unwrap()]);
+    assert!(result.status.success());
+
+    let minified = fs::read_to_string(&output).unwrap();
+    fs::remove_file(&output).unwrap();
+    assert!(!minified.contains('\n'));
+    assert_eq!(
+        parse_str(&minified).unwrap(),
+        parse_json(input.to_string()).unwrap()
+    );
+}
+
+#[test]
+fn test_minify_to_stdout() {
+    let input = "./tests/custom/valid.json";
+    let result = run(&["minify", input]);
+    assert!(result.status.success());
+    let minified = String::from_utf8(result.stdout).unwrap();
+    assert_eq!(
+        parse_str(&minified).unwrap(),
+        parse_json(input.to_string()).unwrap()
+    );
+}
+
+#[test]
+fn test_minify_rejects_invalid_input() {
+    let output = output_path("fail2.min.json");
+    let result = run(&[
+        "minify",
+        "./tests/json_org_tests/fail2.json",
+        output.to_str().unwrap(),
+    ]);
+    assert!(!result.status.success());
+    assert!(!output.exists());
+    assert!(result.stdout.is_empty());
+    assert!(String::from_utf8(result.stderr)
+        .unwrap()
+        .contains("fail2.json:1:"));
+}
@@ -0,0 +1,21 @@
+use std::process::Command;
+
+#[test]
+fn test_pretty_rejects_huge_indent() {
+    let result = Command::new(env!("CARGO_BIN_EXE_cc-json-parser"))
+        .args([
+            "pretty",
+            "--indent",
+            "70000",
+            "--width",
+            "0",
+            "./tests/custom/valid.json",
+        ])
+        .output()
+        .expect("Failed to run cc-json-parser");
+    assert_eq!(result.status.code(), Some(1));
+    assert!(result.stdout.is_empty());
+    let stderr = String::from_utf8(result.stderr).unwrap();
+    assert!(stderr.contains("--indent can be at most 16"));
+    assert!(stderr.contains("usage:"));
+}
@@ -0,0 +1,20 @@
+#!/bin/sh
+# Replaces tests/json_test_suite with upstream JSONTestSuite's test_parsing files, copied
+# byte for byte, along with upstream's LICENSE. The commit they came from is written to
+# tests/json_test_suite/UPSTREAM. Pass a commit to pin one, the default is upstream's master.
+set -eu
+
+commit=${1:-master}
+suite=$(cd "$(dirname "$0")" && pwd)/json_test_suite
+checkout=$(mktemp -d)
+trap 'rm -rf "$checkout"' EXIT
+
+git clone --quiet https://github.com/nst/JSONTestSuite.git "$checkout"
+git -C "$checkout" checkout --quiet "$commit"
+
+rm -rf "$suite"
+mkdir "$suite"
+cp "$checkout"/test_parsing/*.json "$suite"/
+cp "$checkout"/LICENSE "$suite"/LICENSE
+git -C "$checkout" rev-parse HEAD > "$suite"/UPSTREAM
+echo "Copied JSONTestSuite $(cat "$suite"/UPSTREAM)"